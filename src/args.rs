@@ -1,19 +1,22 @@
-use clap::{Parser};
-use crate::parser::{directory_parser, size_parser};
+use clap::{Parser, Subcommand};
+use crate::parser::{directory_parser, size_parser, threshold_parser};
 
 #[derive(Parser, PartialEq, Debug)]
 #[command(version)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Use JavaScript instead of TypeScript (use --language=typescript to switch back)
-    #[arg(long, short, value_name = "LANG", value_parser = ["typescript", "javascript"])]
+    #[arg(long, short, global = true, value_name = "LANG", value_parser = ["typescript", "javascript"])]
     pub language: Option<String>,
 
-    /// The name of the react component for the icon e.g EyeIcon
+    /// The name of the react component for the icon e.g EyeIcon, you are asked for it when it is left out
     #[arg(
         long,
         short,
     )]
-    pub icon_name: String,
+    pub icon_name: Option<String>,
 
     /// The path to the file on your computer or the online url
     #[arg(
@@ -22,10 +25,23 @@ pub struct Args {
     )]
     pub path: Option<String>,
 
+    /// Which svg to use when the page at --path holds several: its number in the list or its id e.g 2 or #logo
+    #[arg(long, value_name = "INDEX|ID", conflicts_with = "all")]
+    pub select: Option<String>,
+
+    /// Convert every svg of the page at --path, each one named after its id
+    #[arg(long, conflicts_with = "copy")]
+    pub all: bool,
+
+    /// Add the svgs to sprite.svg as symbols, drawn by an <Icon name="..."/> component, instead of one component each
+    #[arg(long, conflicts_with = "copy")]
+    pub sprite: bool,
+
     /// The destination folder of the icon
     #[arg(
         long,
         short,
+        global = true,
         value_parser = directory_parser
     )]
     pub destination: Option<String>,
@@ -35,15 +51,126 @@ pub struct Args {
         arg(
             long,
             short,
+            global = true,
             value_parser = size_parser
         )
     ]
     pub size: Option<u32>,
 
+    /// The default color of the icon e.g #3B82F6, also painted into the images of --png and --ico
+    #[arg(long, global = true, value_name = "COLOR")]
+    pub color: Option<String>,
+
+    /// Rasterize the svg to PNGs of these sizes instead of generating a component e.g 16,32,180,512
+    #[arg(long, value_name = "SIZES", value_delimiter = ',', num_args = 1.., value_parser = size_parser, conflicts_with_all = ["copy", "sprite"])]
+    pub png: Option<Vec<u32>>,
+
+    /// Rasterize the svg to a multi-resolution .ico of the --png sizes up to 256, else 16, 32 and 48
+    #[arg(long, conflicts_with_all = ["copy", "sprite"])]
+    pub ico: bool,
+
+    /// Render the component next to the svg and fail when more than this percentage of the pixels differ
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        num_args = 0..=1,
+        default_missing_value = "0.5",
+        value_parser = threshold_parser,
+        conflicts_with_all = ["sprite", "png", "ico"]
+    )]
+    pub verify: Option<f64>,
+
+    /// Copy the result to the clipboard instead of saving a file: the whole component or only the jsx svg
+    #[arg(
+        long,
+        short,
+        value_name = "WHAT",
+        num_args = 0..=1,
+        default_missing_value = "component",
+        value_parser = ["component", "jsx"]
+    )]
+    pub copy: Option<String>,
+
+    /// Use this config file instead of the nearest quickicon.json
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<String>,
+
+    /// Only use the copies of remote svgs in the cache, never the network e.g in CI
+    #[arg(long, global = true)]
+    pub offline: bool,
+
     /// Remember the folder destination and the language for subsequent icons
     #[arg(
         long,
         short = 'D'
     )]
     pub default: bool,
-}
\ No newline at end of file
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum Command {
+    /// Set up quickicon.json for this project by answering a few questions
+    Init,
+
+    /// Keep converting every new svg copied to the clipboard until Ctrl-C
+    WatchClipboard {
+        /// Name the components automatically instead of asking for each name
+        #[arg(long)]
+        auto_name: bool,
+
+        /// How often to check the clipboard, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+
+    /// Regenerate the components whenever an svg in a folder is created, changed or deleted
+    Watch {
+        /// The folder holding the raw svgs e.g design/icons
+        source: String,
+
+        /// How long a burst of saves has to settle before regenerating, in milliseconds
+        #[arg(long, default_value_t = 300)]
+        debounce: u64,
+    },
+
+    /// Rebuild every icon recorded in quickicon.lock.json e.g after updating QuickIcon
+    Regenerate,
+
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Inspect or clean the cache of remote svgs
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Fail when a generated icon is out of date or was edited by hand, like cargo fmt --check
+    Check {
+        /// Check the svgs in this folder against the destination folder instead of the lockfile
+        #[arg(long)]
+        source: Option<String>,
+    },
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum CacheAction {
+    /// List the cached responses, the most recent first
+    List,
+
+    /// Remove cached responses
+    Prune {
+        /// Only remove the ones not fetched for this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum ConfigAction {
+    /// Print the effective config and the file each value came from
+    Show,
+}
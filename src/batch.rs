@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+
+use dialoguer::console::style;
+
+use crate::{
+    content::{fetch_svgs, page_svg_source, read_sprite, Source},
+    convert::SvgToReact,
+    default::Config,
+    iconify,
+    lockfile::record_generated,
+    parser::{component_name_parser, svg_attribute},
+    raster::{self, RasterOptions},
+    sprite::{icon_id, write_sheet},
+    verify,
+};
+
+/// One component to generate as part of a batch
+pub struct BatchItem {
+    pub name: String,
+    pub source: Source,
+    pub svg: String,
+}
+
+/// Every svg of the page, each one named after its id or file name, else the base name and its position
+pub async fn page_items(url: &str, base_name: &str, config: &Config) -> Result<Vec<BatchItem>, Box<dyn std::error::Error>> {
+    let svgs = fetch_svgs(url).await?;
+    let mut items = Vec::new();
+    for (index, found) in svgs.iter().enumerate() {
+        let asset_name = found.asset.as_deref().and_then(|asset| {
+            let path = asset.split(['?', '#']).next()?;
+            Path::new(path).file_stem().map(|stem| stem.to_string_lossy().to_string())
+        });
+        let name = svg_attribute(&found.svg, "id")
+            .or(asset_name)
+            .and_then(|id| component_name_parser(&id, &config.name_suffix).ok())
+            .map(|component_name| Ok(component_name.name))
+            .unwrap_or_else(|| component_name_parser(&format!("{} {}", base_name, index + 1), &config.name_suffix).map(|component_name| component_name.name))?;
+        items.push(BatchItem {
+            name,
+            source: page_svg_source(url, &svgs, index),
+            svg: found.svg.clone(),
+        });
+    }
+    Ok(items)
+}
+
+/// Every icon of an Iconify icon set, each one named after the icon
+pub fn icon_set_items(path: &str, config: &Config) -> Result<Vec<BatchItem>, Box<dyn std::error::Error>> {
+    let set = iconify::load(Path::new(path))?;
+    let mut items = Vec::new();
+    for icon in set.icon_names() {
+        let name = component_name_parser(&icon, &config.name_suffix)?.name;
+        items.push(BatchItem {
+            name,
+            source: Source::Iconify { path: path.to_string(), icon: Some(icon.clone()) },
+            svg: set.render(&icon)?,
+        });
+    }
+    Ok(items)
+}
+
+/// Every symbol of a sprite sheet, each one named after its id
+pub fn sprite_items(path: &str, config: &Config) -> Result<Vec<BatchItem>, Box<dyn std::error::Error>> {
+    let mut items = Vec::new();
+    for symbol in read_sprite(path)? {
+        items.push(BatchItem {
+            name: component_name_parser(&symbol.id, &config.name_suffix)?.name,
+            source: Source::Sprite { path: path.to_string(), symbol: Some(symbol.id) },
+            svg: symbol.svg,
+        });
+    }
+    Ok(items)
+}
+
+/// Generates a component for every item, reporting each one and carrying on past the ones that fail.
+/// With a --verify threshold, the components that do not render like their svg are not saved.
+pub fn generate_all(root: &Path, items: Vec<BatchItem>, config: &Config, verify: Option<f64>) -> Vec<PathBuf> {
+    let total = items.len();
+    let mut generated = Vec::new();
+    for item in items {
+        if let Some(threshold) = verify {
+            match verify::verify(&item.name, &item.svg, config, threshold) {
+                Ok(verification) if verification.passed() => {},
+                Ok(verification) => {
+                    println!("{} {}: {}", style("✘").red(), item.name, style(verification.summary()).red());
+                    continue;
+                },
+                Err(err) => {
+                    println!("{} {}: {}", style("✘").red(), item.name, style(err).red());
+                    continue;
+                },
+            }
+        }
+        match SvgToReact::new(item.svg.clone(), item.name.clone(), config.clone()).convert_and_save() {
+            Ok(path) => {
+                println!("{} {}", style("✔").green(), path.display());
+                if let Err(err) = record_generated(root, &path, &item.name, item.source, &item.svg, config) {
+                    println!("The icon could not be recorded in the lockfile: {}", style(err).yellow());
+                }
+                generated.push(path);
+            },
+            Err(err) => println!("{} {}: {}", style("✘").red(), item.name, style(err).red()),
+        }
+    }
+    println!("{}", style(format!("🎉 {} of {} icon(s) generated", generated.len(), total)).green());
+    generated
+}
+
+/// Collects every item into the sprite sheet of the destination folder instead of one component each
+pub fn generate_sheet(items: Vec<BatchItem>, config: &Config) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
+    let icons: Vec<(String, String)> = items.into_iter().map(|item| (item.name, item.svg)).collect();
+    let (sheet_path, component_path) = write_sheet(&icons, config)?;
+    for (name, _) in &icons {
+        println!("{} {}", style("✔").green(), icon_id(name, config));
+    }
+    println!(
+        "{}",
+        style(format!("🎉 {} icon(s) added to {}, draw them with {}", icons.len(), sheet_path.display(), component_path.display())).green()
+    );
+    Ok((sheet_path, component_path))
+}
+
+/// Rasterizes every item to PNGs and an .ico, reporting each one and carrying on past the ones that fail.
+/// Returns how many of the items were rasterized.
+pub fn generate_raster(items: Vec<BatchItem>, options: &RasterOptions, config: &Config) -> usize {
+    let total = items.len();
+    let mut written = Vec::new();
+    let mut exported = 0;
+    for item in items {
+        match raster::export(&item.name, &item.svg, options, config) {
+            Ok(paths) => {
+                for path in &paths {
+                    println!("{} {}", style("✔").green(), path.display());
+                }
+                written.extend(paths);
+                exported += 1;
+            },
+            Err(err) => println!("{} {}: {}", style("✘").red(), item.name, style(err).red()),
+        }
+    }
+    println!("{}", style(format!("🎉 {} of {} icon(s) rasterized, {} file(s) written", exported, total, written.len())).green());
+    exported
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use dialoguer::console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::hash::sha256_hex;
+
+/// The environment variable that moves the cache e.g to a folder CI keeps between runs
+pub static CACHE_DIR_ENV: &str = "QUICKICON_CACHE_DIR";
+
+/// What is known about a cached response, its body being stored next to it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CacheEntry {
+    pub url: String,
+    /// Where the body came from once the redirects were followed
+    pub final_url: String,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the response was last fetched or revalidated, in seconds since the Unix epoch
+    pub fetched_at: u64,
+    pub size: u64,
+}
+
+impl CacheEntry {
+    /// How long ago the response was fetched or revalidated
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.fetched_at))
+    }
+}
+
+/// The folder of the cache: $QUICKICON_CACHE_DIR when it is set, else quickicon/http in the user cache folder
+pub fn cache_folder() -> Option<PathBuf> {
+    std::env::var_os(CACHE_DIR_ENV)
+        .map(PathBuf::from)
+        .or_else(|| dirs::cache_dir().map(|folder| folder.join("quickicon").join("http")))
+}
+
+/// Reads the cached response of a url, if there is one
+pub fn load(url: &str) -> Option<(CacheEntry, Vec<u8>)> {
+    let (meta_path, body_path) = entry_paths(url)?;
+    let entry: CacheEntry = serde_json::from_str(&fs::read_to_string(meta_path).ok()?).ok()?;
+    let body = fs::read(body_path).ok()?;
+    Some((entry, body))
+}
+
+/// Stores the response of a url, replacing the previous one
+pub fn store(entry: &CacheEntry, body: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let (meta_path, body_path) = entry_paths(&entry.url).ok_or("There is no cache folder on this system")?;
+    if let Some(folder) = meta_path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    fs::write(body_path, body)?;
+    fs::write(meta_path, serde_json::to_string_pretty(entry)?)?;
+    Ok(())
+}
+
+/// Marks a cached response as still valid, after the server answered 304 Not Modified
+pub fn touch(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (meta_path, _) = entry_paths(url).ok_or("There is no cache folder on this system")?;
+    let mut entry: CacheEntry = serde_json::from_str(&fs::read_to_string(&meta_path)?)?;
+    entry.fetched_at = now();
+    fs::write(meta_path, serde_json::to_string_pretty(&entry)?)?;
+    Ok(())
+}
+
+/// Every cached response, the most recently fetched first
+pub fn list() -> Result<Vec<CacheEntry>, Box<dyn std::error::Error>> {
+    let Some(folder) = cache_folder().filter(|folder| folder.is_dir()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut entries: Vec<CacheEntry> = fs::read_dir(folder)?
+        .filter_map(|file| file.ok().map(|file| file.path()))
+        .filter(|path| path.extension().map(|extension| extension == "json").unwrap_or(false))
+        .filter_map(|path| serde_json::from_str(&fs::read_to_string(path).ok()?).ok())
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.fetched_at));
+    Ok(entries)
+}
+
+/// Removes the cached responses not fetched for longer than the age, or all of them without one
+pub fn prune(older_than: Option<Duration>) -> Result<Vec<CacheEntry>, Box<dyn std::error::Error>> {
+    let mut removed = Vec::new();
+    for entry in list()? {
+        if older_than.map(|age| entry.age() < age).unwrap_or(false) {
+            continue;
+        }
+        if let Some((meta_path, body_path)) = entry_paths(&entry.url) {
+            fs::remove_file(meta_path)?;
+            let _ = fs::remove_file(body_path);
+        }
+        removed.push(entry);
+    }
+    Ok(removed)
+}
+
+/// Prints every cached response with its size and age
+pub fn show_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let entries = list()?;
+    let folder = cache_folder().map(|folder| folder.display().to_string()).unwrap_or_default();
+    if entries.is_empty() {
+        println!("The cache in {} is empty", folder);
+        return Ok(());
+    }
+
+    println!("{}", style(format!("{} cached response(s) in {}", entries.len(), folder)).bold());
+    for entry in entries {
+        let validator = if entry.etag.is_some() || entry.last_modified.is_some() { "" } else { ", not revalidated" };
+        println!(
+            "  {:>9}  {:<14} {}{}",
+            format!("{:.1} KB", entry.size as f64 / 1024.0),
+            format_age(entry.age()),
+            entry.url,
+            style(validator).dim()
+        );
+    }
+    Ok(())
+}
+
+/// A rough age e.g 3 days ago
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} hours ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
+/// The seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// The metadata and body files of a url, named after the sha256 of the url
+fn entry_paths(url: &str) -> Option<(PathBuf, PathBuf)> {
+    let key = sha256_hex(url);
+    let folder = cache_folder()?;
+    Some((folder.join(format!("{}.json", key)), folder.join(format!("{}.body", key))))
+}
@@ -0,0 +1,109 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use dialoguer::console::style;
+
+use crate::{
+    content::Source,
+    convert::{component_file_path, SvgToReact},
+    default::Config,
+    lockfile::{hash_svg, Lockfile},
+    parser::{component_name_parser, svg_validator},
+};
+
+/// A generated component that does not match what QuickIcon would generate now
+pub struct Mismatch {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Renders every recorded icon in memory and compares it with the file on disk.
+/// With a source folder the svgs in it are checked against the destination folder instead of the lockfile.
+pub async fn check(root: &Path, source: Option<&Path>, config: &Config) -> Result<Vec<Mismatch>, Box<dyn std::error::Error>> {
+    match source {
+        Some(source) => check_source_folder(source, config),
+        None => check_lockfile(root).await,
+    }
+}
+
+/// Checks every icon recorded in the lockfile
+async fn check_lockfile(root: &Path) -> Result<Vec<Mismatch>, Box<dyn std::error::Error>> {
+    let lockfile = Lockfile::load(root)?;
+    if lockfile.icons.is_empty() {
+        return Err(format!("There are no icons recorded in {}", lockfile.path().display()).into());
+    }
+
+    let mut mismatches = Vec::new();
+    for (output, entry) in &lockfile.icons {
+        let output_path = lockfile.resolve(output);
+        if entry.source == Source::Clipboard {
+            println!("{} {} was generated from the clipboard and cannot be checked", style("⚠️").yellow(), output);
+            continue;
+        }
+
+        match lockfile.render_entry(entry).await {
+            Ok((svg, component)) => {
+                let reason = if hash_svg(&svg) != entry.hash {
+                    Some("the source svg changed since it was generated".to_string())
+                } else {
+                    compare_with_disk(&output_path, &component)
+                };
+                if let Some(reason) = reason {
+                    mismatches.push(Mismatch { path: output_path, reason });
+                }
+            },
+            Err(err) => mismatches.push(Mismatch {
+                path: output_path,
+                reason: format!("the source could not be read: {}", err),
+            }),
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Checks that every svg in the source folder has an up to date component in the destination folder
+fn check_source_folder(source: &Path, config: &Config) -> Result<Vec<Mismatch>, Box<dyn std::error::Error>> {
+    let mut svg_paths: Vec<PathBuf> = fs::read_dir(source)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|extension| extension == "svg").unwrap_or(false))
+        .collect();
+    svg_paths.sort();
+
+    let mut mismatches = Vec::new();
+    for svg_path in svg_paths {
+        let stem = svg_path.file_stem().unwrap_or_default().to_string_lossy();
+        let component_name = match component_name_parser(&stem, &config.name_suffix) {
+            Ok(component_name) => component_name.name,
+            Err(err) => {
+                mismatches.push(Mismatch { path: svg_path.clone(), reason: err });
+                continue;
+            }
+        };
+        let output_path = component_file_path(&component_name, config);
+
+        let svg = fs::read_to_string(&svg_path)?;
+        if !svg_validator(&svg) {
+            mismatches.push(Mismatch {
+                path: output_path,
+                reason: format!("{} does not contain a valid svg element", svg_path.display()),
+            });
+            continue;
+        }
+
+        let component = SvgToReact::new(svg, component_name, config.clone()).render_component()?;
+        if let Some(reason) = compare_with_disk(&output_path, &component) {
+            mismatches.push(Mismatch { path: output_path, reason });
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Compares the component on disk byte for byte with the freshly rendered one
+fn compare_with_disk(path: &Path, component: &str) -> Option<String> {
+    match fs::read(path) {
+        Ok(on_disk) if on_disk == component.as_bytes() => None,
+        Ok(_) => Some("it differs from the generated output (edited by hand or generated by another QuickIcon version)".to_string()),
+        Err(_) => Some("it is missing".to_string()),
+    }
+}
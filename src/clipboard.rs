@@ -0,0 +1,236 @@
+use std::{fmt, io::Write, process::{Command, Stdio}};
+
+use arboard::Clipboard;
+use dialoguer::console::style;
+
+use crate::parser::{extract_svg, svg_validator};
+
+/// The ways using the clipboard can fail
+#[derive(Debug, PartialEq)]
+pub enum ClipboardError {
+    /// There is no X11 or Wayland display to talk to
+    NoDisplay,
+    /// The clipboard holds something other than text e.g an image
+    NonText,
+    /// The clipboard has nothing in it
+    Empty,
+    /// The clipboard exists but could not be read
+    Unavailable(String),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::NoDisplay => write!(
+                f,
+                "No X11 or Wayland display was found, so the clipboard cannot be used. Run QuickIcon inside a graphical session or work with files instead (--path)."
+            ),
+            ClipboardError::NonText => write!(
+                f,
+                "Your clipboard holds non-text data (e.g an image). Copy the svg markup itself, not the rendered icon."
+            ),
+            ClipboardError::Empty => write!(
+                f,
+                "Your clipboard is empty. Copy an svg first or pass one with --path."
+            ),
+            ClipboardError::Unavailable(reason) => write!(
+                f,
+                "The clipboard could not be used ({}). On Linux install xclip, xsel or wl-clipboard.",
+                reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+/// Reads an svg from the clipboard, preferring the image/svg+xml flavor over plain text and html
+pub fn read_svg() -> Result<String, ClipboardError> {
+    if let Some(svg) = read_flavor_with_binaries(Some("image/svg+xml")).filter(|svg| svg_validator(svg)) {
+        return Ok(svg);
+    }
+
+    let text = read_text();
+    if let Ok(text) = &text {
+        if svg_validator(text) {
+            return Ok(text.clone());
+        }
+    }
+
+    if let Some(svg) = read_html().and_then(|html| extract_svg(&html)) {
+        return Ok(svg);
+    }
+
+    text
+}
+
+/// Reads the text on the clipboard, falling back to the clipboard binaries when arboard fails
+pub fn read_text() -> Result<String, ClipboardError> {
+    match read_text_with_arboard() {
+        Ok(text) => Ok(text),
+        Err(err) => read_flavor_with_binaries(None).ok_or(err),
+    }
+}
+
+/// Puts text on the clipboard so it outlives QuickIcon.
+/// On Linux the clipboard belongs to the process that set it, so the clipboard binaries, which keep running
+/// in the background, are tried first. Without them arboard keeps serving the text until something else is copied.
+pub fn write_text(text: &str) -> Result<(), ClipboardError> {
+    let copied = copy_binaries()
+        .iter()
+        .any(|(program, args)| run_copy_binary(program, args, text));
+    if copied {
+        return Ok(());
+    }
+
+    let mut clipboard = Clipboard::new().map_err(|err| {
+        if has_display() {
+            ClipboardError::Unavailable(err.to_string())
+        } else {
+            ClipboardError::NoDisplay
+        }
+    })?;
+    set_text_with_arboard(&mut clipboard, text).map_err(|err| ClipboardError::Unavailable(err.to_string()))
+}
+
+/// How long QuickIcon serves the copied text on Linux when no clipboard binary or manager takes it over
+#[cfg(all(unix, not(target_os = "macos")))]
+static COPY_WAIT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Sets the text through arboard, waiting on Linux until another program takes the clipboard over or COPY_WAIT runs out
+#[cfg(all(unix, not(target_os = "macos")))]
+fn set_text_with_arboard(clipboard: &mut Clipboard, text: &str) -> Result<(), arboard::Error> {
+    use arboard::SetExtLinux;
+
+    println!(
+        "{}",
+        style(format!(
+            "Keeping the result on the clipboard for {} seconds or until something else is copied, install wl-clipboard, xclip or xsel to keep it longer",
+            COPY_WAIT.as_secs()
+        ))
+        .dim()
+    );
+    clipboard.set().wait_until(std::time::Instant::now() + COPY_WAIT).text(text)
+}
+
+/// Sets the text through arboard, the system keeping it once QuickIcon exits
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn set_text_with_arboard(clipboard: &mut Clipboard, text: &str) -> Result<(), arboard::Error> {
+    clipboard.set_text(text)
+}
+
+/// Reads the text/html flavor of the clipboard e.g what browsers and Figma put there
+fn read_html() -> Option<String> {
+    Clipboard::new()
+        .ok()
+        .and_then(|mut clipboard| clipboard.get().html().ok())
+        .filter(|html| !html.trim().is_empty())
+        .or_else(|| read_flavor_with_binaries(Some("text/html")))
+}
+
+/// Reads the text on the clipboard through arboard
+fn read_text_with_arboard() -> Result<String, ClipboardError> {
+    let mut clipboard = Clipboard::new().map_err(|err| {
+        if has_display() {
+            ClipboardError::Unavailable(err.to_string())
+        } else {
+            ClipboardError::NoDisplay
+        }
+    })?;
+
+    match clipboard.get_text() {
+        Ok(text) if text.trim().is_empty() => Err(ClipboardError::Empty),
+        Ok(text) => Ok(text),
+        Err(arboard::Error::ContentNotAvailable) => {
+            if clipboard.get_image().is_ok() {
+                Err(ClipboardError::NonText)
+            } else {
+                Err(ClipboardError::Empty)
+            }
+        },
+        Err(arboard::Error::ConversionFailure) => Err(ClipboardError::NonText),
+        Err(err) => Err(ClipboardError::Unavailable(err.to_string())),
+    }
+}
+
+/// Reads the clipboard through wl-paste, xclip or xsel, whichever works first.
+/// Without a mime type the plain text flavor is read.
+fn read_flavor_with_binaries(mime: Option<&str>) -> Option<String> {
+    clipboard_binaries(mime)
+        .iter()
+        .find_map(|(program, args)| run_clipboard_binary(program, args))
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// The clipboard binaries to try, in order, with the arguments that print the clipboard
+fn clipboard_binaries(mime: Option<&str>) -> Vec<(&'static str, Vec<&str>)> {
+    if !cfg!(all(unix, not(target_os = "macos"))) {
+        return vec![];
+    }
+
+    match mime {
+        Some(mime) => vec![
+            ("wl-paste", vec!["--no-newline", "--type", mime]),
+            ("xclip", vec!["-selection", "clipboard", "-t", mime, "-o"]),
+        ],
+        None => vec![
+            ("wl-paste", vec!["--no-newline"]),
+            ("xclip", vec!["-selection", "clipboard", "-o"]),
+            ("xsel", vec!["--clipboard", "--output"]),
+        ],
+    }
+}
+
+/// The clipboard binaries to try, in order, with the arguments that fill the clipboard from stdin
+fn copy_binaries() -> Vec<(&'static str, Vec<&'static str>)> {
+    if !cfg!(all(unix, not(target_os = "macos"))) {
+        return vec![];
+    }
+
+    vec![
+        ("wl-copy", vec![]),
+        ("xclip", vec!["-selection", "clipboard", "-i"]),
+        ("xsel", vec!["--clipboard", "--input"]),
+    ]
+}
+
+/// Pipes text into a clipboard binary and reports whether it succeeded
+fn run_copy_binary(program: &str, args: &[&str], text: &str) -> bool {
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match child {
+        Ok(mut child) => {
+            let written = child
+                .stdin
+                .take()
+                .map(|mut stdin| stdin.write_all(text.as_bytes()).is_ok())
+                .unwrap_or(false);
+            written && child.wait().map(|status| status.success()).unwrap_or(false)
+        },
+        Err(_) => false,
+    }
+}
+
+/// Runs a clipboard binary and returns its output if it succeeded
+fn run_clipboard_binary(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if output.status.success() {
+        String::from_utf8(output.stdout).ok()
+    } else {
+        None
+    }
+}
+
+/// Checks if there is a display server the clipboard could live on
+fn has_display() -> bool {
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some()
+    } else {
+        true
+    }
+}
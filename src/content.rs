@@ -1,63 +1,400 @@
-use std::{fs, path::PathBuf};
-use crate::{args::Args, parser::svg_validator};
-use arboard::Clipboard;
+use std::{fs, io::Read, path::{Path, PathBuf}};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::read::GzDecoder;
 use regex::Regex;
+use serde::{Serialize, Deserialize};
+use crate::{clipboard, fetch::{self, FetchOptions}, iconify, sprite::{self, Symbol}, parser::{find_svg_assets, find_svgs, svg_attribute, svg_summary, svg_validator}};
+use dialoguer::console::style;
 
-pub async fn get_content(args: &Args) -> Result<String, Box<dyn std::error::Error>> {    
-    match &args.path {
-        Some(path) => {
-            // What I want to do here is read the file or read the url
-
-            if path.starts_with("https://") || path.starts_with("www.") {
-                let response = reqwest::get(path).await?;
-                let body = response.text().await?;
-
-                let pattern = r#"(?s)<svg[^>]*>.*?</svg>"#;
-
-                let re = Regex::new(pattern)?;
-                let tag = re.captures(&body);
-                match tag {
-                    None => {
-                        return Err("There is no svg returned from the provided url".into());
-                    },
-                    Some(svg_tag) => {
-                        return Ok(svg_tag[0].to_string());
-                    }
-                }
-            } else {
-                let path = PathBuf::try_from(path).unwrap();
-                
-                if let Some(extension) = path.extension() {
-                    if extension == "svg" || extension == "txt" {
-                        match fs::read_to_string(path) {
-                            Ok(content) => {
-                                if svg_validator(&content) {
-                                    Ok(content)
-                                } else {
-                                    Err("The file you provided does not contain a valid svg element.".into())
-                                }
-                            },
-                            Err(_err) => {
-                                Err("An error occurred while reading the provided svg file".into())
-                            }
-                        }    
+/// Where the svg of an icon comes from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Source {
+    File { path: String },
+    Url {
+        url: String,
+        /// Which svg of the page to use when it holds several: a 1-based index or an id
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        select: Option<String>,
+    },
+    /// A <symbol> of a local svg sprite sheet
+    Sprite {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        symbol: Option<String>,
+    },
+    /// An icon of a local Iconify icon set
+    Iconify {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        icon: Option<String>,
+    },
+    Clipboard,
+}
+
+impl Source {
+    /// Works out the source from the --path argument, the clipboard being used when there is none
+    pub fn from_path(path: Option<&str>) -> Self {
+        match path {
+            Some(path) if fetch::is_url(path) => Source::Url { url: path.to_string(), select: None },
+            Some(path) if path.ends_with(".json") && iconify::is_icon_set(Path::new(path)) => Source::Iconify { path: path.to_string(), icon: None },
+            Some(path) if path.ends_with(".svg") && fs::read_to_string(path).map(|svg| sprite::is_sprite(&svg)).unwrap_or(false) => {
+                Source::Sprite { path: path.to_string(), symbol: None }
+            },
+            Some(path) => Source::File { path: path.to_string() },
+            None => Source::Clipboard,
+        }
+    }
+
+    /// Sets which svg of the page, symbol of the sprite or icon of the set to use, which only makes sense for those
+    pub fn with_select(self, select: Option<String>) -> Result<Self, String> {
+        match (self, select) {
+            (Source::Url { url, .. }, Some(select)) => Ok(Source::Url { url, select: Some(select) }),
+            (Source::Sprite { path, .. }, Some(symbol)) => Ok(Source::Sprite { path, symbol: Some(symbol.trim_start_matches('#').to_string()) }),
+            (Source::Iconify { path, .. }, Some(icon)) => Ok(Source::Iconify { path, icon: Some(icon) }),
+            (_, Some(_)) => Err("--select only works when --path is a url, a sprite or an Iconify icon set".to_string()),
+            (source, None) => Ok(source),
+        }
+    }
+}
+
+/// Reads the svg from the file, the url or the clipboard
+pub async fn get_source_content(source: &Source) -> Result<String, Box<dyn std::error::Error>> {
+    match source {
+        Source::Url { url, select } => choose_page_svg(&fetch_svgs(url).await?, select.as_deref()),
+        Source::Sprite { path, symbol } => {
+            let symbols = read_sprite(path)?;
+            match (symbol, symbols.as_slice()) {
+                (None, [only]) => Ok(only.svg.clone()),
+                (None, symbols) => Err(format!(
+                    "{} is a sprite of {} symbols, choose one with --select <id> or convert them all with --all",
+                    path,
+                    symbols.len()
+                ).into()),
+                (Some(id), symbols) => symbols
+                    .iter()
+                    .find(|symbol| &symbol.id == id)
+                    .map(|symbol| symbol.svg.clone())
+                    .ok_or_else(|| format!("There is no symbol with the id {} in {}", id, path).into()),
+            }
+        },
+        Source::Iconify { path, icon: Some(icon) } => iconify::load(Path::new(path))?.render(icon),
+        Source::Iconify { path, icon: None } => {
+            let set = iconify::load(Path::new(path))?;
+            Err(format!(
+                "{} holds the {} icons of the {} set, choose one with --select <name> or convert them all with --all",
+                path,
+                set.icon_names().len(),
+                set.prefix
+            ).into())
+        },
+        Source::File { path } => {
+            let path = PathBuf::from(path);
+
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("svg" | "svgz" | "txt") => {
+                    let bytes = fs::read(&path)
+                        .map_err(|_err| "An error occurred while reading the provided svg file")?;
+                    let content = decode_svg(&bytes)
+                        .map_err(|err| format!("{} could not be decoded: {}", path.display(), err))?;
+                    if svg_validator(&content) {
+                        Ok(content)
                     } else {
-                        Err("Only .svg or .txt files are allowed".into())
+                        Err("The file you provided does not contain a valid svg element.".into())
                     }
-                } else {
-                    Err("File has no extension and only .svg or .txt files are allowed".into())
-                }
+                },
+                Some(_) => Err("Only .svg, .svgz or .txt files are allowed".into()),
+                None => Err("File has no extension and only .svg, .svgz or .txt files are allowed".into()),
             }
-        }, 
+        },
+        Source::Clipboard => read_clipboard_svg(),
+    }
+}
+
+/// The largest svg a gzipped or base64 input may expand to, in bytes
+static MAX_DECODED_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Reads the svg on the clipboard, decoding it when it was copied as a data URI or base64 e.g from CSS
+pub fn read_clipboard_svg() -> Result<String, Box<dyn std::error::Error>> {
+    let clipboard_text_content = decode_svg_text(&clipboard::read_svg()?)?;
+    if svg_validator(&clipboard_text_content) {
+        Ok(clipboard_text_content)
+    } else {
+        Err("Your clipboard text content is not a valid svg.".into())
+    }
+}
+
+/// Turns the bytes of a file into svg markup, gunzipping a .svgz and decoding a data URI or base64 text
+pub fn decode_svg(bytes: &[u8]) -> Result<String, String> {
+    let bytes = gunzip(bytes)?;
+    let text = String::from_utf8(bytes).map_err(|_err| "the content is not UTF-8 text".to_string())?;
+    decode_svg_text(&text)
+}
+
+/// Decodes text holding a data URI e.g data:image/svg+xml;base64,PHN2Zy... or data:image/svg+xml,%3Csvg...,
+/// possibly still wrapped in the url() it was copied from, or bare base64. Anything else is returned as it is.
+pub fn decode_svg_text(text: &str) -> Result<String, String> {
+    let trimmed = text.trim();
+    let unwrapped = Regex::new(r#"(?is)^url\(\s*['"]?(.*?)['"]?\s*\)\s*;?$"#)
+        .unwrap()
+        .captures(trimmed)
+        .map(|found| found[1].trim().to_string())
+        .unwrap_or_else(|| trimmed.to_string());
+
+    if unwrapped.len() >= 5 && unwrapped[..5].eq_ignore_ascii_case("data:") {
+        return decode_data_uri(&unwrapped);
+    }
+
+    // Bare base64 only counts when it decodes to an svg, plain text being left alone
+    let is_base64 = Regex::new(r"^[A-Za-z0-9+/=\s]+$").unwrap().is_match(&unwrapped);
+    if is_base64 && !unwrapped.is_empty() {
+        let compact: String = unwrapped.split_whitespace().collect();
+        if let Some(svg) = STANDARD
+            .decode(compact)
+            .ok()
+            .and_then(|bytes| gunzip(&bytes).ok())
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .filter(|svg| svg_validator(svg))
+        {
+            return Ok(svg);
+        }
+    }
+    Ok(text.to_string())
+}
+
+/// Decodes a data URI, its payload being base64 or URL-encoded
+fn decode_data_uri(uri: &str) -> Result<String, String> {
+    let (header, payload) = uri[5..]
+        .split_once(',')
+        .ok_or("the data URI has no comma before its data")?;
+    let mut parameters = header.split(';').map(str::trim);
+    let media_type = parameters.next().unwrap_or_default().to_lowercase();
+    if !media_type.is_empty() && media_type != "image/svg+xml" && media_type != "text/plain" {
+        return Err(format!("the data URI holds {}, not an svg", media_type));
+    }
+
+    let bytes = if parameters.any(|parameter| parameter.eq_ignore_ascii_case("base64")) {
+        // Base64 copied out of a URL can itself be URL-encoded, and lines can be wrapped
+        let payload = percent_encoding::percent_decode_str(payload).decode_utf8_lossy();
+        let compact: String = payload.split_whitespace().collect();
+        STANDARD
+            .decode(compact)
+            .map_err(|err| format!("the base64 data of the data URI is not valid: {}", err))?
+    } else {
+        percent_encoding::percent_decode_str(payload).collect()
+    };
+    let bytes = gunzip(&bytes)?;
+    String::from_utf8(bytes).map_err(|_err| "the data URI does not hold UTF-8 text".to_string())
+}
+
+/// Gunzips the bytes when they start with the gzip magic number, as .svgz files do
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return Ok(bytes.to_vec());
+    }
+
+    let mut decoded = Vec::new();
+    GzDecoder::new(bytes)
+        .take(MAX_DECODED_SIZE + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|err| format!("the gzipped data is not valid: {}", err))?;
+    if decoded.len() as u64 > MAX_DECODED_SIZE {
+        return Err(format!("the gzipped data expands past the {} MB limit", MAX_DECODED_SIZE / 1024 / 1024));
+    }
+    Ok(decoded)
+}
+
+/// The most svg files linked from a page that are downloaded
+static MAX_ASSETS: usize = 50;
+
+/// An svg found at a url, either inline in the page or in a file the page links to
+#[derive(Debug, Clone)]
+pub struct PageSvg {
+    pub svg: String,
+    /// The url of the linked file, None for an svg inline in the page
+    pub asset: Option<String>,
+}
+
+impl PageSvg {
+    /// Describes the svg for the picker e.g 24×24, id=heart, 0.1 KB, from https://example.com/heart.svg
+    pub fn summary(&self) -> String {
+        match &self.asset {
+            Some(asset) => format!("{}, from {}", svg_summary(&self.svg), asset),
+            None => svg_summary(&self.svg),
+        }
+    }
+}
+
+/// Fetches the url and finds every svg in it. An svg image is taken as it is, a page gives its top level
+/// svgs followed by the svg files it links to through <img src> or CSS url(), resolved against the page url.
+pub async fn fetch_svgs(url: &str) -> Result<Vec<PageSvg>, Box<dyn std::error::Error>> {
+    let options = FetchOptions::default();
+    let page = fetch::fetch(url, &options).await?;
+    let body = page.text();
+    if page.is_svg() {
+        if !svg_validator(&body) {
+            return Err("The url is served as an svg image but does not contain a valid svg element".into());
+        }
+        return Ok(vec![PageSvg { svg: body, asset: None }]);
+    }
+
+    let mut svgs: Vec<PageSvg> = find_svgs(&body)
+        .into_iter()
+        .map(|svg| PageSvg { svg, asset: None })
+        .collect();
+
+    for asset in find_svg_assets(&body).into_iter().take(MAX_ASSETS) {
+        let Ok(asset_url) = page.url.join(&asset) else {
+            continue;
+        };
+        match fetch::fetch(asset_url.as_str(), &options).await {
+            Ok(fetched) if svg_validator(&fetched.text()) => svgs.push(PageSvg {
+                svg: fetched.text(),
+                asset: Some(asset_url.to_string()),
+            }),
+            Ok(_) => println!("{} {} does not contain a valid svg element, skipping it", style("⚠️").yellow(), asset_url),
+            Err(err) => println!("{} {} was skipped: {}", style("⚠️").yellow(), asset_url, err),
+        }
+    }
+
+    if svgs.is_empty() {
+        return Err("There is no svg returned from the provided url".into());
+    }
+    Ok(svgs)
+}
+
+/// Picks the svg of the page the selector points at, there being no need for one when the page holds a single svg
+pub fn choose_page_svg(svgs: &[PageSvg], select: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    match select {
+        Some(selector) => select_svg(svgs, selector),
+        None if svgs.len() == 1 => Ok(svgs[0].svg.clone()),
         None => {
-            let mut clipboard = Clipboard::new().unwrap();
-            let clipboard_text_content = clipboard.get_text().unwrap();
-            if svg_validator(&clipboard_text_content) {
-                Ok(clipboard_text_content)
-            } else {
-                Err("Your clipboard text content is not a valid svg.".into())
-            }
-            
+            let choices: Vec<String> = svgs
+                .iter()
+                .enumerate()
+                .map(|(index, found)| format!("  {}. {}", index + 1, found.summary()))
+                .collect();
+            Err(format!(
+                "The page holds {} svgs, choose one with --select <index|id> or extract them all with --all:\n{}",
+                svgs.len(),
+                choices.join("\n")
+            ).into())
         }
     }
 }
+
+/// Picks the svg the selector points at: a 1-based index e.g 2, or an id with or without the # e.g #logo
+pub fn select_svg(svgs: &[PageSvg], selector: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if let Ok(index) = selector.parse::<usize>() {
+        return index
+            .checked_sub(1)
+            .and_then(|index| svgs.get(index))
+            .map(|found| found.svg.clone())
+            .ok_or_else(|| format!("There is no svg number {}, the page holds {}", index, svgs.len()).into());
+    }
+
+    let id = selector.trim_start_matches('#');
+    svgs.iter()
+        .find(|found| svg_attribute(&found.svg, "id").as_deref() == Some(id))
+        .map(|found| found.svg.clone())
+        .ok_or_else(|| format!("There is no svg with the id {} on the page", id).into())
+}
+
+/// The source that finds the chosen svg again later: the linked file itself, else the page along with the id
+/// of the svg when it is unique, or its position
+pub fn page_svg_source(url: &str, svgs: &[PageSvg], index: usize) -> Source {
+    let found = &svgs[index];
+    if let Some(asset) = &found.asset {
+        return Source::Url { url: asset.clone(), select: None };
+    }
+
+    let select = match svg_attribute(&found.svg, "id") {
+        Some(id) if svgs.iter().filter(|other| svg_attribute(&other.svg, "id").as_deref() == Some(id.as_str())).count() == 1 => format!("#{}", id),
+        _ => (index + 1).to_string(),
+    };
+    Source::Url { url: url.to_string(), select: Some(select) }
+}
+
+/// Reads a sprite sheet and splits it into its symbols
+pub fn read_sprite(path: &str) -> Result<Vec<Symbol>, Box<dyn std::error::Error>> {
+    let sprite = fs::read_to_string(path)
+        .map_err(|err| format!("The sprite {} could not be read: {}", path, err))?;
+    let symbols = sprite::split_sprite(&sprite);
+    if symbols.is_empty() {
+        return Err(format!("There is no symbol with an id in {}", path).into());
+    }
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    static SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path d="M0 0h24"/></svg>"#;
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decodes_a_base64_data_uri() {
+        let uri = format!("data:image/svg+xml;base64,{}", STANDARD.encode(SVG));
+        assert_eq!(decode_svg_text(&uri).unwrap(), SVG);
+    }
+
+    #[test]
+    fn decodes_a_percent_encoded_data_uri() {
+        let uri = "data:image/svg+xml,%3Csvg%20xmlns%3D%22http%3A%2F%2Fwww.w3.org%2F2000%2Fsvg%22%20viewBox%3D%220%200%2024%2024%22%3E%3Cpath%20d%3D%22M0%200h24%22%2F%3E%3C%2Fsvg%3E";
+        assert_eq!(decode_svg_text(uri).unwrap(), SVG);
+    }
+
+    #[test]
+    fn unwraps_the_url_of_a_css_declaration() {
+        let declaration = format!("url(\"data:image/svg+xml;base64,{}\");", STANDARD.encode(SVG));
+        assert_eq!(decode_svg_text(&declaration).unwrap(), SVG);
+        let single_quoted = format!("url('data:image/svg+xml;utf8,{}')", SVG);
+        assert_eq!(decode_svg_text(&single_quoted).unwrap(), SVG);
+    }
+
+    #[test]
+    fn decodes_bare_base64() {
+        let encoded = STANDARD.encode(SVG);
+        let wrapped: Vec<&str> = encoded.as_bytes().chunks(20).map(|line| std::str::from_utf8(line).unwrap()).collect();
+        assert_eq!(decode_svg_text(&wrapped.join("\n")).unwrap(), SVG);
+        assert_eq!(decode_svg_text(&STANDARD.encode(gzip(SVG.as_bytes()))).unwrap(), SVG);
+    }
+
+    #[test]
+    fn leaves_plain_text_as_it_is() {
+        assert_eq!(decode_svg_text(SVG).unwrap(), SVG);
+        // Words that happen to be valid base64 but do not decode to an svg
+        assert_eq!(decode_svg_text("HeartIcon").unwrap(), "HeartIcon");
+    }
+
+    #[test]
+    fn refuses_a_data_uri_of_another_media_type() {
+        let err = decode_svg_text("data:image/png;base64,iVBORw0KGgo=").unwrap_err();
+        assert!(err.contains("image/png"), "{}", err);
+        assert!(decode_svg_text("data:image/svg+xml;base64").is_err());
+    }
+
+    #[test]
+    fn gunzips_svgz_files() {
+        assert_eq!(decode_svg(&gzip(SVG.as_bytes())).unwrap(), SVG);
+        assert_eq!(decode_svg(SVG.as_bytes()).unwrap(), SVG);
+    }
+
+    #[test]
+    fn stops_gunzipping_past_the_size_limit() {
+        let bomb = gzip(&vec![b' '; MAX_DECODED_SIZE as usize + 1]);
+        let err = gunzip(&bomb).unwrap_err();
+        assert!(err.contains("10 MB"), "{}", err);
+        assert_eq!(gunzip(&gzip(&vec![b' '; MAX_DECODED_SIZE as usize])).unwrap().len() as u64, MAX_DECODED_SIZE);
+    }
+}
@@ -1,8 +1,11 @@
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
 
 use regex::Regex;
 
-use crate::default::Config;
+use crate::{default::Config, parser::to_kebab_case};
+
+/// The first line of every generated component, used to tell them apart from hand written ones
+pub static GENERATED_MARKER: &str = "// Generated by QuickIcon";
 
 pub struct SvgToReact {
     svg_string: String,
@@ -18,12 +21,60 @@ impl SvgToReact {
     /// Processes the svg, generates the component and save the component to a file
     pub fn convert_and_save(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
         self.check_component_existence()?;
-        let processed_svg = self.process_svg()?;
-        let component = self.wrap_in_component(&processed_svg);
+        let component = self.render_component()?;
+        let path = self.save_to_file(&component)?;
+        Ok(path)
+    }
+
+    /// Processes the svg and saves the component, replacing an existing one only if QuickIcon generated it
+    pub fn convert_and_overwrite(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let file_path = self.generate_file_path();
+        if file_path.exists() && !is_generated(&file_path) {
+            return Err(format!("{:?} was not generated by QuickIcon, so it was left untouched", file_path).into());
+        }
+        let component = self.render_component()?;
         let path = self.save_to_file(&component)?;
         Ok(path)
     }
+
+    /// Processes the svg and returns the full component source without saving it
+    pub fn render_component(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let processed_svg = self.process_svg()?;
+        Ok(self.wrap_in_component(&processed_svg))
+    }
+
+    /// Processes the svg and returns only the jsx svg element
+    pub fn render_jsx(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let processed_svg = self.process_svg()?;
+        Ok(self.indent_svg(&processed_svg, 4, 0))
+    }
     
+    /// The svg the component draws with its default size and color, written back as plain svg e.g to render it for --verify
+    pub fn render_svg(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut svg = self
+            .process_svg()?
+            .replacen(" {...props}", "", 1)
+            .replace("{size}", &format!("\"{}\"", self.config.size))
+            .replace("{color}", &format!("\"{}\"", self.config.color));
+
+        for (html_attr, xml_attr) in self.get_attributes().iter() {
+            let re = Regex::new(&format!(r#"\b{}="#, regex::escape(xml_attr)))?;
+            svg = re.replace_all(&svg, format!("{}=", html_attr)).to_string();
+        }
+
+        let style_re = Regex::new(r#"style=\{\{ (.*?) \}\}"#)?;
+        let property_re = Regex::new(r#"(\w+):\s*'([^']*)'"#)?;
+        Ok(style_re
+            .replace_all(&svg, |caps: &regex::Captures| {
+                let style_string: Vec<String> = property_re
+                    .captures_iter(&caps[1])
+                    .map(|property| format!("{}:{}", to_kebab_case(&property[1]), &property[2]))
+                    .collect();
+                format!(r#"style="{}""#, style_string.join(";"))
+            })
+            .to_string())
+    }
+
     /// Convert attributes, extract colors and dimensions
     fn process_svg(&self) -> Result<String, Box<dyn std::error::Error>> {
         let mut svg = self.svg_string.clone();
@@ -117,7 +168,7 @@ impl SvgToReact {
         let re = Regex::new(r#"style="([^"]*)""#)?;
         Ok(re.replace_all(svg, |caps: &regex::Captures| {
             let style_string = &caps[1];
-            let style_object = self.parse_style_to_object(&style_string);
+            let style_object = self.parse_style_to_object(style_string);
             format!("style={{{{ {} }}}}", style_object)
         }).to_string())
     }
@@ -209,7 +260,7 @@ impl SvgToReact {
     }
 
     fn wrap_in_component(&self, processed_svg: &str) -> String {
-        let indented_svg = self.indent_svg(processed_svg, 4);
+        let indented_svg = self.indent_svg(processed_svg, 4, 2);
 
         let mut import_line = format!(r#"import React, {{SVGProps}} from "react";
 
@@ -225,10 +276,11 @@ interface {}Props extends SVGProps<SVGSVGElement> {{
         
         format!(
             r##"{}
+{}
 
 const {} = ({{ 
     size = {}, 
-    color = '#111827', 
+    color = '{}', 
     ...props
 }}{}) => {{
     return (
@@ -244,9 +296,11 @@ export default {};
 // <{} size="32" color="#3B82F6" />
 // <{} className="hover:opacity-80" />
 "##,
+            GENERATED_MARKER,
             import_line,
             self.component_name,
             self.config.size,
+            self.config.color,
             props_type,
             indented_svg,
             self.component_name,
@@ -258,9 +312,9 @@ export default {};
     }
 
     /// Indent SVG content
-    fn indent_svg(&self, svg: &str, spaces: usize) -> String {        
+    fn indent_svg(&self, svg: &str, spaces: usize, level: usize) -> String {        
         let mut result = String::new();
-        let mut level = 2;
+        let mut level = level;
         let indent_str = " ".repeat(spaces);
 
         // Split by tags but keep them
@@ -274,9 +328,7 @@ export default {};
 
         for token in tokens {
             if token.starts_with("</") {
-                if level > 0 {
-                    level -= 1;
-                }
+                level = level.saturating_sub(1);
                 result.push_str(&format!("{}{}\n", indent_str.repeat(level), token));
             } else if token.starts_with("<") && token.ends_with("/>") {
                 result.push_str(&format!("{}{}\n", indent_str.repeat(level), token));
@@ -302,11 +354,7 @@ export default {};
 
     /// It generates the full file path
     fn generate_file_path(&self) -> PathBuf {
-                let mut file_name = format!("{}.tsx", self.component_name);
-        if self.config.is_javascript {
-            file_name = format!("{}.jsx", self.component_name);
-        }
-        self.config.destination_folder.join(file_name)
+        component_file_path(&self.component_name, &self.config)
     }
 
     fn check_component_existence(&self) -> Result<(), String> {
@@ -318,4 +366,20 @@ export default {};
         }
     }
 
-}
\ No newline at end of file
+}
+
+/// The path a component with the given name is saved to
+pub fn component_file_path(component_name: &str, config: &Config) -> PathBuf {
+    let mut file_name = format!("{}.tsx", component_name);
+    if config.is_javascript {
+        file_name = format!("{}.jsx", component_name);
+    }
+    config.destination_folder.join(file_name)
+}
+
+/// Checks if the component at the path was generated by QuickIcon
+pub fn is_generated(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| content.starts_with(GENERATED_MARKER))
+        .unwrap_or(false)
+}
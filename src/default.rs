@@ -1,57 +1,503 @@
-use std::{fs, path::PathBuf};
+use std::{fs, path::{Component, Path, PathBuf}};
 
-use crate::args::Args;
+use crate::{args::Args, parser::is_identifier_part, project::detect_project};
+use dialoguer::console::style;
 use serde::{Serialize, Deserialize};
-use serde_json;
+use serde_json::{Map, Value};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The name of the project config file
+pub static CONFIG_FILE_NAME: &str = "quickicon.json";
+
+/// The config files QuickIcon reads from the project root, from the lowest to the highest precedence
+pub static CONFIG_FILES: [&str; 4] = ["package.json", ".quickiconrc", "quickicon.toml", CONFIG_FILE_NAME];
+
+/// The config files QuickIcon reads from the user config folder, from the lowest to the highest precedence
+pub static USER_CONFIG_FILES: [&str; 2] = ["config.toml", "config.json"];
+
+/// The prefix of the environment variables overriding the config e.g QUICKICON_SIZE
+pub static ENV_PREFIX: &str = "QUICKICON_";
+
+/// Any field missing from the config file takes its default value
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Config {
     pub is_javascript: bool,
     pub destination_folder: PathBuf,
     pub size: u32,
+    pub color: String,
+    /// Added to component names that do not already end with it e.g Icon
+    pub name_suffix: String,
+    /// Put in front of the symbol ids of the sprite sheet so they do not clash with other ids of the page
+    pub sprite_prefix: String,
+    /// The url the sprite sheet is served from, worked out from the public folder when left empty
+    pub sprite_url: String,
 }
 
-/// Get the config if it's not provided and saves it as default if specified in the argument
-pub fn get_and_save_config(args: &Args) -> Result<Config, Box<dyn std::error::Error>> {
-    let config_file_path = PathBuf::from("./quickicon.json");
-    let default_destination = PathBuf::from("./public/assets/icon");
-    let default_size = 24;
-    
-    let mut config = if config_file_path.exists() {
-        serde_json::from_str(&fs::read_to_string(&config_file_path)?).unwrap_or_else(|_| Config {
-            is_javascript: false,
-            destination_folder: default_destination,
-            size: default_size
-        })
-    } else {
+impl Default for Config {
+    fn default() -> Self {
         Config {
             is_javascript: false,
-            destination_folder: default_destination,
-            size: default_size
+            destination_folder: PathBuf::from("./public/assets/icon"),
+            size: 24,
+            color: "#111827".to_string(),
+            name_suffix: String::new(),
+            sprite_prefix: "icon-".to_string(),
+            sprite_url: String::new(),
+        }
+    }
+}
+
+/// The config values coming from one place e.g a config file or the command line flags
+#[derive(Clone)]
+pub struct Layer {
+    pub origin: String,
+    pub values: Map<String, Value>,
+}
+
+/// Every config key along with the place its value came from
+pub type Origins = Vec<(String, String)>;
+
+/// Get the config if it's not provided and saves it as default if specified in the argument,
+/// along with where each value came from
+pub fn get_and_save_config(args: &Args) -> Result<(Config, Origins), Box<dyn std::error::Error>> {
+    let root = project_root(args);
+    let project_layers = project_layers(args)?;
+    let (config, origins) = merge_layers(&layers_around(args, &root, project_layers.clone())?)?;
+
+    if args.default {
+        // Only what belongs to the project is shared, the user config and the environment variables stay personal
+        let mut layers = base_layers(&root, &project_layers);
+        layers.extend(project_layers);
+        layers.push(flag_layer(args));
+        let (mut saved_config, _) = merge_layers(&layers)?;
+
+        let config_file_path = save_config_path(args);
+        saved_config.destination_folder = relative_path(&root, &saved_config.destination_folder);
+        save_config(&config_file_path, &saved_config)?;
+    }
+
+    Ok((config, origins))
+}
+
+/// Puts the other layers around the ones of the project config files, from the lowest to the highest precedence:
+/// the defaults, what was detected in the project when it has no config file, the user config files,
+/// the project config files, the environment variables and the flags
+fn layers_around(args: &Args, root: &Path, project_layers: Vec<Layer>) -> Result<Vec<Layer>, Box<dyn std::error::Error>> {
+    let mut layers = base_layers(root, &project_layers);
+
+    if let Some(folder) = user_config_folder() {
+        for path in USER_CONFIG_FILES.iter().map(|name| folder.join(name)).filter(|path| path.is_file()) {
+            if let Some(layer) = read_config_layer(&path, root)? {
+                layers.push(layer);
+            }
         }
+    }
+
+    layers.extend(project_layers);
+    layers.extend(env_layers(root)?);
+    layers.push(flag_layer(args));
+    Ok(layers)
+}
+
+/// The defaults, their destination starting from the project root, along with what was detected in the project
+/// when it has no config file
+fn base_layers(root: &Path, project_layers: &[Layer]) -> Vec<Layer> {
+    let mut defaults = to_map(&Config::default());
+    rebase_destination(&mut defaults, root);
+    let mut layers = vec![Layer { origin: "default".to_string(), values: defaults }];
+    if project_layers.is_empty() {
+        layers.extend(detected_layer(root));
+    }
+    layers
+}
+
+/// The layers of the project config files, the --config one or the ones in the project root
+fn project_layers(args: &Args) -> Result<Vec<Layer>, Box<dyn std::error::Error>> {
+    let root = project_root(args);
+    let config_files: Vec<PathBuf> = match &args.config {
+        Some(path) if !Path::new(path).exists() && !args.default => {
+            return Err(format!("The config file {} does not exist", path).into());
+        },
+        Some(path) => vec![PathBuf::from(path)].into_iter().filter(|path| path.exists()).collect(),
+        None => project_config_files(&root),
     };
-    
-    if let Some(dest) = &args.destination {
-        config.destination_folder = PathBuf::from(dest);
+
+    let mut layers = Vec::new();
+    for path in config_files {
+        let folder = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        if let Some(layer) = read_config_layer(&path, &folder)? {
+            layers.push(layer);
+        }
     }
+    Ok(layers)
+}
 
-    if let Some(lang) = &args.language {
-        config.is_javascript = lang == "javascript";
+/// The language and the destination inferred from the project: TypeScript when there is a tsconfig.json
+/// and the first of the usual icon folders that exists
+fn detected_layer(root: &Path) -> Option<Layer> {
+    let project = detect_project(root);
+    if !project.is_detected() {
+        return None;
     }
 
+    let mut values = Map::new();
+    values.insert("is_javascript".to_string(), Value::Bool(!project.has_typescript));
+    if let Some(folder) = project.icons_folder {
+        values.insert("destination_folder".to_string(), Value::String(folder.to_string_lossy().to_string()));
+    }
+    Some(Layer { origin: "detected".to_string(), values })
+}
+
+/// Merges the layers into the config, along with the origin of every value
+pub fn merge_layers(layers: &[Layer]) -> Result<(Config, Origins), Box<dyn std::error::Error>> {
+    let mut merged = Map::new();
+    let mut origins: Origins = Vec::new();
+
+    for layer in layers {
+        for (key, value) in &layer.values {
+            merged.insert(key.clone(), value.clone());
+            match origins.iter_mut().find(|(origin_key, _)| origin_key == key) {
+                Some((_, origin)) => *origin = layer.origin.clone(),
+                None => origins.push((key.clone(), layer.origin.clone())),
+            }
+        }
+    }
+
+    let config: Config = serde_json::from_value(Value::Object(merged))?;
+    if !is_identifier_part(&config.name_suffix) {
+        let origin = origins.iter().find(|(key, _)| key == "name_suffix").map(|(_, origin)| origin.as_str()).unwrap_or("default");
+        return Err(format!("The name_suffix {:?} ({}) can only hold letters, digits, _ and $", config.name_suffix, origin).into());
+    }
+    Ok((config, origins))
+}
+
+/// Prints the effective config and where each value came from, as get_and_save_config merged them
+pub fn show_config(args: &Args, config: &Config, origins: &Origins) {
+    let values = to_map(config);
+
+    println!("{}", style(format!("Project root: {}", project_root(args).display())).bold());
+    for (key, origin) in origins {
+        let value = values.get(key).map(Value::to_string).unwrap_or_default();
+        println!("  {:<20} {:<30} {}", key, value, style(format!("({})", origin)).dim());
+    }
+    for alias in detect_project(&project_root(args)).path_aliases {
+        println!("  {:<20} {:<30} {}", "tsconfig alias", alias.prefix, style(format!("({})", alias.folder.display())).dim());
+    }
+}
+
+/// Reads one config file into a layer. package.json only counts when it has a "quickicon" key.
+/// A relative destination starts from the base folder: the folder of a project config file, the project root for a user one.
+fn read_config_layer(path: &Path, base: &Path) -> Result<Option<Layer>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+
+    // Deserializing the text into a config, rather than going through a value, keeps the line and column in errors
+    let value = if path.ends_with("package.json") {
+        let package: Value = serde_json::from_str(&content)
+            .map_err(|err| format!("{} is not valid JSON: {}", path.display(), err))?;
+        match package.get("quickicon") {
+            Some(value) => {
+                serde_json::from_value::<Config>(value.clone())
+                    .map_err(|err| format!("The \"quickicon\" key of {} has an invalid value: {}", path.display(), err))?;
+                value.clone()
+            },
+            None => return Ok(None),
+        }
+    } else if is_toml(path) {
+        toml::from_str::<Config>(&content)
+            .map_err(|err| format!("{} is not a valid config: {}", path.display(), err))?;
+        toml::from_str::<Value>(&content)?
+    } else {
+        let value: Value = serde_json::from_str(&content)
+            .map_err(|err| format!("{} is not valid JSON: {}", path.display(), err))?;
+        serde_json::from_str::<Config>(&content)
+            .map_err(|err| format!("{} has an invalid value: {}", path.display(), err))?;
+        value
+    };
+
+    let mut values = match value {
+        Value::Object(map) => map,
+        _ => return Err(format!("{} must hold an object of settings", path.display()).into()),
+    };
+
+    for key in unknown_keys(&values) {
+        println!(
+            "{}",
+            style(format!("⚠️  Unknown key `{}` in {} was ignored, the known keys are: {}", key, path.display(), known_keys().join(", "))).yellow()
+        );
+        values.remove(&key);
+    }
+
+    rebase_destination(&mut values, base);
+    Ok(Some(Layer { origin: path.display().to_string(), values }))
+}
+
+/// The layers of the QUICKICON_* environment variables, one for every config key that has one set.
+/// A relative destination starts from the project root, like the one of a user config file.
+fn env_layers(root: &Path) -> Result<Vec<Layer>, Box<dyn std::error::Error>> {
+    let mut layers = Vec::new();
+    for (key, default_value) in to_map(&Config::default()) {
+        let name = format!("{}{}", ENV_PREFIX, key.to_uppercase());
+        let Ok(raw_value) = std::env::var(&name) else {
+            continue;
+        };
+        // Booleans and numbers are read as JSON, text settings are taken as they are
+        let value = if default_value.is_string() {
+            Value::String(raw_value)
+        } else {
+            serde_json::from_str::<Value>(&raw_value).unwrap_or(Value::String(raw_value))
+        };
+
+        let mut values = Map::new();
+        values.insert(key, value);
+        serde_json::from_value::<Config>(Value::Object(values.clone()))
+            .map_err(|err| format!("The environment variable {} has an invalid value: {}", name, err))?;
+        rebase_destination(&mut values, root);
+        layers.push(Layer { origin: name, values });
+    }
+    Ok(layers)
+}
+
+/// Makes a relative destination start from the base folder rather than from where QuickIcon runs
+fn rebase_destination(values: &mut Map<String, Value>, base: &Path) {
+    if let Some(Value::String(destination)) = values.get("destination_folder") {
+        let destination = relative_path(Path::new("."), &base.join(destination));
+        values.insert("destination_folder".to_string(), Value::String(destination.to_string_lossy().to_string()));
+    }
+}
+
+/// The user level config folder e.g ~/.config/quickicon, following $XDG_CONFIG_HOME when it is set
+pub fn user_config_folder() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|folder| folder.is_absolute())
+        .or_else(dirs::config_dir)
+        .map(|folder| folder.join("quickicon"))
+}
+
+/// The layer of the values given as command line flags
+fn flag_layer(args: &Args) -> Layer {
+    let mut values = Map::new();
+    if let Some(dest) = &args.destination {
+        values.insert("destination_folder".to_string(), Value::String(dest.clone()));
+    }
+    if let Some(lang) = &args.language {
+        values.insert("is_javascript".to_string(), Value::Bool(lang == "javascript"));
+    }
     if let Some(size) = args.size {
-        config.size = size
+        values.insert("size".to_string(), Value::from(size));
     }
-    
-    if args.default {
-        save_config(&config_file_path, &config)?;
+    if let Some(color) = &args.color {
+        values.insert("color".to_string(), Value::String(color.clone()));
+    }
+    Layer { origin: "command line flags".to_string(), values }
+}
+
+fn to_map(config: &Config) -> Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// The keys a config file can have
+fn known_keys() -> Vec<String> {
+    to_map(&Config::default()).keys().cloned().collect()
+}
+
+/// The keys of a config file that QuickIcon does not know about e.g typos
+fn unknown_keys(values: &Map<String, Value>) -> Vec<String> {
+    let known_keys = known_keys();
+    values
+        .keys()
+        .filter(|key| !known_keys.contains(key))
+        .cloned()
+        .collect()
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().map(|extension| extension == "toml").unwrap_or(false)
+}
+
+/// The config files present in the folder, from the lowest to the highest precedence
+fn project_config_files(root: &Path) -> Vec<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// The file --default writes to: the --config one unless it is a package.json, else quickicon.json in the project root
+fn save_config_path(args: &Args) -> PathBuf {
+    match &args.config {
+        Some(path) if !path.ends_with("package.json") => PathBuf::from(path),
+        _ => project_root(args).join(CONFIG_FILE_NAME),
+    }
+}
+
+/// The folder holding the config, relative paths in the config and the lockfile start from it.
+/// It is the folder of the --config file, else the nearest folder walking up from the current one with a config file.
+pub fn project_root(args: &Args) -> PathBuf {
+    let root = match &args.config {
+        Some(path) => Path::new(path).parent().map(Path::to_path_buf),
+        None => std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_config_folder(&cwd))
+            .map(|folder| relative_path(Path::new("."), &folder)),
+    };
+    match root {
+        Some(folder) if !folder.as_os_str().is_empty() => folder,
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Walks up from the folder until one with a config file is found
+pub fn find_config_folder(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|folder| {
+            project_config_files(folder)
+                .iter()
+                .any(|path| !path.ends_with("package.json") || has_package_config(path))
+        })
+        .map(Path::to_path_buf)
+}
+
+/// Checks if a package.json has a "quickicon" key
+fn has_package_config(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .map(|package| package.get("quickicon").is_some())
+        .unwrap_or(false)
+}
+
+/// Expresses the path relative to the base folder e.g ../src/icons, both being relative to the current folder
+/// or absolute
+pub fn relative_path(base: &Path, path: &Path) -> PathBuf {
+    let base = absolute_path(base);
+    let path = absolute_path(path);
+
+    let common = base
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // Paths on different drives have nothing in common, so only the absolute path works
+    if common == 0 {
+        return path;
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push("..");
+    }
+    for component in path.components().skip(common) {
+        relative.push(component);
+    }
+
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else if relative.starts_with("..") {
+        relative
+    } else {
+        PathBuf::from(".").join(relative)
     }
-    
-    Ok(config)
 }
 
-fn save_config(path: &PathBuf, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let json_config = serde_json::to_string(config)?;
-    fs::write(path, json_config)?;
+/// Makes the path absolute and resolves the . and .. in it without touching the filesystem
+fn absolute_path(path: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut absolute = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                absolute.pop();
+            },
+            component => absolute.push(component),
+        }
+    }
+    absolute
+}
+
+fn save_config(path: &Path, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let serialized_config = if is_toml(path) {
+        toml::to_string(config)?
+    } else {
+        serde_json::to_string(config)?
+    };
+    fs::write(path, serialized_config)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh folder in the temp folder for one test
+    fn temp_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("quickicon-default-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn user_config_destination_starts_from_the_project_root() {
+        let folder = temp_folder("user");
+        let user_config = folder.join("config.json");
+        fs::write(&user_config, r#"{"destination_folder": "src/icons"}"#).unwrap();
+        let root = folder.join("project");
+
+        let layer = read_config_layer(&user_config, &root).unwrap().unwrap();
+        let destination = layer.values["destination_folder"].as_str().unwrap();
+        assert_eq!(absolute_path(Path::new(destination)), absolute_path(&root.join("src/icons")));
+    }
+
+    #[test]
+    fn project_config_destination_starts_from_its_folder() {
+        let folder = temp_folder("project");
+        let project_config = folder.join(CONFIG_FILE_NAME);
+        fs::write(&project_config, r#"{"destination_folder": "./src/icons", "size": 20}"#).unwrap();
+
+        let layer = read_config_layer(&project_config, &folder).unwrap().unwrap();
+        let destination = layer.values["destination_folder"].as_str().unwrap();
+        assert_eq!(absolute_path(Path::new(destination)), absolute_path(&folder.join("src/icons")));
+        assert_eq!(layer.values["size"], Value::from(20));
+    }
+
+    #[test]
+    fn environment_destination_starts_from_the_project_root() {
+        let mut values = Map::new();
+        values.insert("destination_folder".to_string(), Value::String("src/icons".to_string()));
+        rebase_destination(&mut values, Path::new("../monorepo/app"));
+
+        let destination = values["destination_folder"].as_str().unwrap();
+        assert_eq!(absolute_path(Path::new(destination)), absolute_path(Path::new("../monorepo/app/src/icons")));
+    }
+
+    #[test]
+    fn absolute_destination_is_kept() {
+        let mut values = Map::new();
+        values.insert("destination_folder".to_string(), Value::String("/tmp/icons".to_string()));
+        rebase_destination(&mut values, Path::new("../monorepo/app"));
+
+        assert_eq!(absolute_path(Path::new(values["destination_folder"].as_str().unwrap())), PathBuf::from("/tmp/icons"));
+    }
+
+    #[test]
+    fn rejects_a_name_suffix_that_is_not_an_identifier() {
+        let mut values = to_map(&Config::default());
+        values.insert("name_suffix".to_string(), Value::String("/../../escaped".to_string()));
+        let layers = vec![Layer { origin: "quickicon.json".to_string(), values }];
+
+        let err = merge_layers(&layers).unwrap_err().to_string();
+        assert!(err.contains("quickicon.json"), "{}", err);
+    }
+}
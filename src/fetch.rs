@@ -0,0 +1,490 @@
+use std::{fmt, sync::atomic::{AtomicBool, Ordering}, time::Duration};
+
+use dialoguer::console::style;
+use regex::Regex;
+use reqwest::{header, redirect::Policy, StatusCode, Url};
+
+use crate::cache::{self, CacheEntry};
+
+/// The User-Agent sent with every request e.g quickicon/0.3.0
+pub static USER_AGENT: &str = concat!("quickicon/", env!("CARGO_PKG_VERSION"));
+
+/// Set by --offline for every request of the run
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Makes every following request served only from the cache
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// The limits a request has to stay within
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    pub max_redirects: usize,
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    /// The largest body accepted, in bytes
+    pub max_body_size: usize,
+    pub user_agent: String,
+    /// Keep responses in the on-disk cache and revalidate them
+    pub use_cache: bool,
+    /// Only serve responses from the cache, never touching the network
+    pub offline: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        FetchOptions {
+            max_redirects: 5,
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            max_body_size: 5 * 1024 * 1024,
+            user_agent: USER_AGENT.to_string(),
+            use_cache: true,
+            offline: OFFLINE.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A successful response
+#[derive(Debug)]
+pub struct Fetched {
+    /// Where the body came from once the redirects were followed
+    pub url: Url,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Fetched {
+    /// The body as text, invalid UTF-8 being replaced
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// Checks if the server said the body is an svg image
+    pub fn is_svg(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .map(|content_type| content_type.trim().to_lowercase().starts_with("image/svg+xml"))
+            .unwrap_or(false)
+    }
+}
+
+/// The ways fetching a url can fail
+#[derive(Debug)]
+pub enum FetchError {
+    InvalidUrl(String),
+    Status { url: Url, status: StatusCode },
+    TooManyRedirects(usize),
+    TooLarge(usize),
+    Timeout,
+    NotCached(Url),
+    Request(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::InvalidUrl(reason) => write!(f, "The url is not valid: {}", reason),
+            FetchError::Status { url, status } => write!(f, "The server answered {} for {}", status, url),
+            FetchError::TooManyRedirects(max) => write!(f, "The url redirected more than {} times", max),
+            FetchError::TooLarge(max) => write!(f, "The response is larger than the {} KB limit", max / 1024),
+            FetchError::Timeout => write!(f, "The server took too long to answer"),
+            FetchError::NotCached(url) => write!(f, "{} is not in the cache, run once without --offline to fetch it", url),
+            FetchError::Request(reason) => write!(f, "The request failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Checks if a --path is meant as a url rather than a file: it has a scheme e.g http:// or starts with www.
+pub fn is_url(s: &str) -> bool {
+    let re = Regex::new(r"^(?i)([a-z][a-z0-9+.-]*://|www\.)").unwrap();
+    re.is_match(s.trim())
+}
+
+/// Parses a http or https url, a bare www. address being taken as https
+pub fn parse_url(s: &str) -> Result<Url, FetchError> {
+    let s = s.trim();
+    let with_scheme = if s.to_lowercase().starts_with("www.") {
+        format!("https://{}", s)
+    } else {
+        s.to_string()
+    };
+
+    let url = Url::parse(&with_scheme).map_err(|err| FetchError::InvalidUrl(format!("{} ({})", s, err)))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(FetchError::InvalidUrl(format!("only http and https are supported, not {}", scheme))),
+    }
+}
+
+/// Downloads the url, following redirects and stopping at the limits of the options.
+/// A cached response is revalidated with its ETag or Last-Modified and used as it is when the server cannot be reached.
+pub async fn fetch(url: &str, options: &FetchOptions) -> Result<Fetched, FetchError> {
+    let url = parse_url(url)?;
+    let cached = if options.use_cache { cache::load(url.as_str()) } else { None };
+
+    if options.offline {
+        return cached.ok_or(FetchError::NotCached(url)).and_then(from_cache);
+    }
+
+    match download(&url, options, cached.as_ref().map(|(entry, _)| entry)).await {
+        Ok(Download::NotModified) => {
+            let _ = cache::touch(url.as_str());
+            cached.ok_or(FetchError::NotCached(url)).and_then(from_cache)
+        },
+        Ok(Download::Fresh { fetched, etag, last_modified }) => {
+            if options.use_cache {
+                let entry = CacheEntry {
+                    url: url.to_string(),
+                    final_url: fetched.url.to_string(),
+                    content_type: fetched.content_type.clone(),
+                    etag,
+                    last_modified,
+                    fetched_at: cache::now(),
+                    size: fetched.body.len() as u64,
+                };
+                if let Err(err) = cache::store(&entry, &fetched.body) {
+                    println!("{} The response could not be cached: {}", style("⚠️").yellow(), err);
+                }
+            }
+            Ok(fetched)
+        },
+        Err(err @ (FetchError::Request(_) | FetchError::Timeout)) => match cached {
+            Some(cached) => {
+                println!("{} {}, using the cached copy of {}", style("⚠️").yellow(), err, url);
+                from_cache(cached)
+            },
+            None => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// What the server answered
+enum Download {
+    NotModified,
+    Fresh {
+        fetched: Fetched,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+/// Sends the request, made conditional when there is a cached response to revalidate
+async fn download(url: &Url, options: &FetchOptions, cached: Option<&CacheEntry>) -> Result<Download, FetchError> {
+    let client = reqwest::Client::builder()
+        .redirect(Policy::limited(options.max_redirects))
+        .connect_timeout(options.connect_timeout)
+        .read_timeout(options.read_timeout)
+        .user_agent(&options.user_agent)
+        .build()
+        .map_err(|err| FetchError::Request(err.to_string()))?;
+
+    let mut request = client.get(url.clone());
+    if let Some(etag) = cached.and_then(|entry| entry.etag.as_deref()) {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = cached.and_then(|entry| entry.last_modified.as_deref()) {
+        request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+    }
+    let mut response = request.send().await.map_err(|err| request_error(err, options))?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED && cached.is_some() {
+        return Ok(Download::NotModified);
+    }
+    if !status.is_success() {
+        return Err(FetchError::Status { url: response.url().clone(), status });
+    }
+    if response.content_length().map(|length| length as usize > options.max_body_size).unwrap_or(false) {
+        return Err(FetchError::TooLarge(options.max_body_size));
+    }
+
+    let header_value = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let content_type = header_value(header::CONTENT_TYPE);
+    let etag = header_value(header::ETAG);
+    let last_modified = header_value(header::LAST_MODIFIED);
+    let final_url = response.url().clone();
+
+    // The Content-Length can be missing or wrong, so the limit is checked while reading too
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|err| request_error(err, options))? {
+        if body.len() + chunk.len() > options.max_body_size {
+            return Err(FetchError::TooLarge(options.max_body_size));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(Download::Fresh {
+        fetched: Fetched { url: final_url, content_type, body },
+        etag,
+        last_modified,
+    })
+}
+
+/// Turns a cached response back into a fetched one, a hand edited or corrupted entry being refused
+fn from_cache((entry, body): (CacheEntry, Vec<u8>)) -> Result<Fetched, FetchError> {
+    let url = Url::parse(&entry.final_url)
+        .or_else(|_| Url::parse(&entry.url))
+        .map_err(|err| FetchError::InvalidUrl(format!("{} in the cache ({})", entry.final_url, err)))?;
+    Ok(Fetched { url, content_type: entry.content_type, body })
+}
+
+/// Sorts out a reqwest error, keeping the underlying cause e.g connection refused
+fn request_error(err: reqwest::Error, options: &FetchOptions) -> FetchError {
+    if err.is_timeout() {
+        return FetchError::Timeout;
+    }
+    if err.is_redirect() {
+        return FetchError::TooManyRedirects(options.max_redirects);
+    }
+
+    let mut reason = err.to_string();
+    let mut source = std::error::Error::source(&err);
+    while let Some(cause) = source {
+        reason = format!("{}: {}", reason, cause);
+        source = cause.source();
+    }
+    FetchError::Request(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+    use super::*;
+
+    /// What the mock server answers: the status line and headers, then the body after a delay
+    struct Reply {
+        head: String,
+        body: Vec<u8>,
+        delay: Duration,
+    }
+
+    impl Reply {
+        fn new(status: &str, headers: &[&str], body: &[u8]) -> Self {
+            let mut head = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+            for header in headers {
+                head.push_str(header);
+                head.push_str("\r\n");
+            }
+            Reply { head: head + "\r\n", body: body.to_vec(), delay: Duration::ZERO }
+        }
+
+        fn svg(body: &str) -> Self {
+            let length = format!("Content-Length: {}", body.len());
+            Reply::new("200 OK", &["Content-Type: image/svg+xml", &length], body.as_bytes())
+        }
+    }
+
+    /// Starts a local server answering every request through the handler, which gets the raw request, and returns its url
+    async fn serve(handler: impl Fn(&str) -> Reply + Send + Sync + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handler = Arc::new(handler);
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buffer = [0; 1024];
+                    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match stream.read(&mut buffer).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => request.extend_from_slice(&buffer[..read]),
+                        }
+                    }
+                    let reply = handler(&String::from_utf8_lossy(&request));
+                    let _ = stream.write_all(reply.head.as_bytes()).await;
+                    let _ = stream.flush().await;
+                    tokio::time::sleep(reply.delay).await;
+                    let _ = stream.write_all(&reply.body).await;
+                });
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    /// The path of the request e.g /icon.svg
+    fn request_path(request: &str) -> &str {
+        request.split_whitespace().nth(1).unwrap_or("/")
+    }
+
+    fn options() -> FetchOptions {
+        FetchOptions { use_cache: false, offline: false, ..FetchOptions::default() }
+    }
+
+    #[tokio::test]
+    async fn fetches_over_http() {
+        let url = serve(|request| {
+            assert!(request.contains(USER_AGENT));
+            Reply::svg("<svg></svg>")
+        })
+        .await;
+
+        let fetched = fetch(&format!("{}/icon.svg", url), &options()).await.unwrap();
+        assert_eq!(fetched.text(), "<svg></svg>");
+        assert!(fetched.is_svg());
+    }
+
+    #[tokio::test]
+    async fn follows_redirects_up_to_the_limit() {
+        let url = serve(|request| {
+            let hops: usize = request_path(request).trim_start_matches("/redirect/").parse().unwrap();
+            if hops == 0 {
+                Reply::svg("<svg></svg>")
+            } else {
+                Reply::new("302 Found", &[&format!("Location: /redirect/{}", hops - 1), "Content-Length: 0"], b"")
+            }
+        })
+        .await;
+        let options = FetchOptions { max_redirects: 2, ..options() };
+
+        let fetched = fetch(&format!("{}/redirect/2", url), &options).await.unwrap();
+        assert!(fetched.url.as_str().ends_with("/redirect/0"));
+
+        let err = fetch(&format!("{}/redirect/3", url), &options).await.unwrap_err();
+        assert!(matches!(err, FetchError::TooManyRedirects(2)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn reports_the_status_of_a_client_error() {
+        let url = serve(|_| Reply::new("404 Not Found", &["Content-Length: 0"], b"")).await;
+
+        let err = fetch(&format!("{}/missing.svg", url), &options()).await.unwrap_err();
+        assert!(matches!(err, FetchError::Status { status: StatusCode::NOT_FOUND, .. }), "{}", err);
+        assert!(err.to_string().contains("404"));
+        assert!(err.to_string().contains("/missing.svg"));
+    }
+
+    #[tokio::test]
+    async fn refuses_a_content_length_over_the_limit() {
+        let url = serve(|_| Reply::new("200 OK", &["Content-Length: 4096"], &[b'a'; 4096])).await;
+        let options = FetchOptions { max_body_size: 1024, ..options() };
+
+        let err = fetch(&url, &options).await.unwrap_err();
+        assert!(matches!(err, FetchError::TooLarge(1024)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn refuses_a_chunked_body_over_the_limit() {
+        let url = serve(|_| {
+            let chunk = "a".repeat(600);
+            let body = format!("{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n", chunk.len(), chunk, chunk.len(), chunk);
+            Reply::new("200 OK", &["Transfer-Encoding: chunked"], body.as_bytes())
+        })
+        .await;
+        let options = FetchOptions { max_body_size: 1024, ..options() };
+
+        let err = fetch(&url, &options).await.unwrap_err();
+        assert!(matches!(err, FetchError::TooLarge(1024)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn times_out_on_a_slow_body() {
+        let url = serve(|_| Reply {
+            delay: Duration::from_secs(5),
+            ..Reply::new("200 OK", &["Content-Length: 11"], b"<svg></svg>")
+        })
+        .await;
+        let options = FetchOptions { read_timeout: Duration::from_millis(200), ..options() };
+
+        let err = fetch(&url, &options).await.unwrap_err();
+        assert!(matches!(err, FetchError::Timeout), "{}", err);
+    }
+
+    /// Points the cache at a folder of its own, the same one for every test so they can run side by side
+    fn use_test_cache() {
+        static CACHE: std::sync::OnceLock<()> = std::sync::OnceLock::new();
+        CACHE.get_or_init(|| {
+            let folder = std::env::temp_dir().join(format!("quickicon-fetch-cache-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&folder);
+            std::env::set_var(cache::CACHE_DIR_ENV, folder);
+        });
+    }
+
+    #[tokio::test]
+    async fn revalidates_the_cached_copy_with_its_etag() {
+        use_test_cache();
+        let not_modified = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = not_modified.clone();
+        let url = serve(move |request| {
+            if request.to_lowercase().contains("if-none-match: \"v1\"") {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Reply::new("304 Not Modified", &["ETag: \"v1\""], b"")
+            } else {
+                Reply::new("200 OK", &["Content-Type: image/svg+xml", "ETag: \"v1\"", "Content-Length: 11"], b"<svg></svg>")
+            }
+        })
+        .await;
+        let options = FetchOptions { use_cache: true, ..options() };
+
+        assert_eq!(fetch(&url, &options).await.unwrap().text(), "<svg></svg>");
+        let revalidated = fetch(&url, &options).await.unwrap();
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+        assert_eq!(revalidated.text(), "<svg></svg>");
+        assert!(revalidated.is_svg());
+    }
+
+    #[tokio::test]
+    async fn offline_serves_the_cache_only() {
+        use_test_cache();
+        let url = serve(|_| Reply::svg("<svg></svg>")).await;
+        let offline = FetchOptions { use_cache: true, offline: true, ..options() };
+
+        let err = fetch(&format!("{}/never-fetched.svg", url), &offline).await.unwrap_err();
+        assert!(matches!(err, FetchError::NotCached(_)), "{}", err);
+
+        let online = FetchOptions { use_cache: true, ..options() };
+        fetch(&format!("{}/fetched.svg", url), &online).await.unwrap();
+        let cached = fetch(&format!("{}/fetched.svg", url), &offline).await.unwrap();
+        assert_eq!(cached.text(), "<svg></svg>");
+    }
+
+    #[tokio::test]
+    async fn refuses_a_corrupted_cache_entry() {
+        use_test_cache();
+        let url = "https://example.com/corrupted.svg";
+        let entry = CacheEntry {
+            url: url.to_string(),
+            final_url: "not a url".to_string(),
+            content_type: None,
+            etag: None,
+            last_modified: None,
+            fetched_at: cache::now(),
+            size: 0,
+        };
+        cache::store(&entry, b"").unwrap();
+        // Edited by hand, the entry no longer holds a url to fall back on either
+        let meta_path = cache::cache_folder().unwrap().join(format!("{}.json", crate::hash::sha256_hex(url)));
+        let edited = CacheEntry { url: "not a url either".to_string(), ..entry };
+        std::fs::write(meta_path, serde_json::to_string(&edited).unwrap()).unwrap();
+
+        let offline = FetchOptions { use_cache: true, offline: true, ..options() };
+        let err = fetch(url, &offline).await.unwrap_err();
+        assert!(matches!(err, FetchError::InvalidUrl(_)), "{}", err);
+    }
+
+    #[test]
+    fn only_takes_http_urls() {
+        assert!(is_url("https://example.com/icon.svg"));
+        assert!(is_url("www.example.com"));
+        assert!(!is_url("icons/star.svg"));
+        assert_eq!(parse_url("www.example.com").unwrap().as_str(), "https://www.example.com/");
+        assert!(matches!(parse_url("ftp://example.com/icon.svg"), Err(FetchError::InvalidUrl(_))));
+    }
+}
@@ -0,0 +1,9 @@
+use sha2::{Digest, Sha256};
+
+/// The sha256 of a text as a hex string, also safe as a file name on any file system
+pub fn sha256_hex(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,327 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::Deserialize;
+
+/// How many aliases can point at each other before the chain is taken as a loop
+static MAX_ALIAS_DEPTH: usize = 10;
+
+/// The default width and height of the icons when the set does not give them
+static DEFAULT_SIZE: f64 = 16.0;
+
+/// An icon set in the @iconify/json format
+#[derive(Deserialize, Debug)]
+pub struct IconSet {
+    pub prefix: String,
+    pub icons: BTreeMap<String, IconData>,
+    #[serde(default)]
+    pub aliases: BTreeMap<String, AliasData>,
+    pub left: Option<f64>,
+    pub top: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+}
+
+/// One icon of the set, its size falling back to the one of the set
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IconData {
+    pub body: String,
+    pub left: Option<f64>,
+    pub top: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    #[serde(default)]
+    pub rotate: i32,
+    #[serde(default)]
+    pub h_flip: bool,
+    #[serde(default)]
+    pub v_flip: bool,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// Another name for an icon, possibly turned or flipped
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasData {
+    pub parent: String,
+    pub left: Option<f64>,
+    pub top: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    #[serde(default)]
+    pub rotate: i32,
+    #[serde(default)]
+    pub h_flip: bool,
+    #[serde(default)]
+    pub v_flip: bool,
+}
+
+/// An icon with its aliases resolved and every default filled in
+struct ResolvedIcon {
+    body: String,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+    rotate: i32,
+    h_flip: bool,
+    v_flip: bool,
+}
+
+/// Reads an icon set file
+pub fn load(path: &Path) -> Result<IconSet, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("The icon set {} could not be read: {}", path.display(), err))?;
+    serde_json::from_str(&content)
+        .map_err(|err| format!("{} is not an Iconify icon set: {}", path.display(), err).into())
+}
+
+/// Checks if a json file looks like an icon set, with a prefix and icons
+pub fn is_icon_set(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .map(|set| set.get("prefix").is_some() && set.get("icons").is_some())
+        .unwrap_or(false)
+}
+
+impl IconSet {
+    /// The names of the icons of the set, leaving out the aliases and the hidden icons
+    pub fn icon_names(&self) -> Vec<String> {
+        self.icons
+            .iter()
+            .filter(|(_, icon)| !icon.hidden)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Checks if the name is an icon or an alias of the set
+    pub fn contains(&self, name: &str) -> bool {
+        self.icons.contains_key(name) || self.aliases.contains_key(name)
+    }
+
+    /// Builds the svg of an icon or an alias, applying its rotation and flips
+    pub fn render(&self, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        if !self.contains(name) {
+            let similar = self.similar_names(name);
+            let hint = if similar.is_empty() { String::new() } else { format!(", did you mean {}?", similar.join(", ")) };
+            return Err(format!("There is no icon named {} in the {} set{}", name, self.prefix, hint).into());
+        }
+        let icon = self.resolve(name, 0)?;
+        let (mut left, mut top, mut width, mut height) = (icon.left, icon.top, icon.width, icon.height);
+        let mut rotate = icon.rotate;
+        let mut transforms = Vec::new();
+
+        // The same steps as Iconify's iconToSVG so the output matches the one of the API
+        if icon.h_flip {
+            if icon.v_flip {
+                rotate += 2;
+            } else {
+                transforms.push(format!("translate({} {})", number(width + left), number(-top)));
+                transforms.push("scale(-1 1)".to_string());
+                left = 0.0;
+                top = 0.0;
+            }
+        } else if icon.v_flip {
+            transforms.push(format!("translate({} {})", number(-left), number(height + top)));
+            transforms.push("scale(1 -1)".to_string());
+            left = 0.0;
+            top = 0.0;
+        }
+
+        match rotate.rem_euclid(4) {
+            1 => {
+                let center = height / 2.0 + top;
+                transforms.insert(0, format!("rotate(90 {} {})", number(center), number(center)));
+            },
+            2 => transforms.insert(0, format!("rotate(180 {} {})", number(width / 2.0 + left), number(height / 2.0 + top))),
+            3 => {
+                let center = width / 2.0 + left;
+                transforms.insert(0, format!("rotate(-90 {} {})", number(center), number(center)));
+            },
+            _ => {},
+        }
+        if rotate.rem_euclid(2) == 1 {
+            std::mem::swap(&mut left, &mut top);
+            std::mem::swap(&mut width, &mut height);
+        }
+
+        let body = if transforms.is_empty() {
+            icon.body
+        } else {
+            format!("<g transform=\"{}\">{}</g>", transforms.join(" "), icon.body)
+        };
+
+        Ok(format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">{}</svg>",
+            number(width),
+            number(height),
+            number(left),
+            number(top),
+            number(width),
+            number(height),
+            body
+        ))
+    }
+
+    /// Follows the aliases down to the icon, adding up the rotations and flips on the way
+    fn resolve(&self, name: &str, depth: usize) -> Result<ResolvedIcon, Box<dyn std::error::Error>> {
+        if depth > MAX_ALIAS_DEPTH {
+            return Err(format!("The alias {} of the {} set points at itself", name, self.prefix).into());
+        }
+
+        if let Some(icon) = self.icons.get(name) {
+            return Ok(ResolvedIcon {
+                body: icon.body.clone(),
+                left: icon.left.or(self.left).unwrap_or(0.0),
+                top: icon.top.or(self.top).unwrap_or(0.0),
+                width: icon.width.or(self.width).unwrap_or(DEFAULT_SIZE),
+                height: icon.height.or(self.height).unwrap_or(DEFAULT_SIZE),
+                rotate: icon.rotate,
+                h_flip: icon.h_flip,
+                v_flip: icon.v_flip,
+            });
+        }
+
+        let alias = self
+            .aliases
+            .get(name)
+            .ok_or_else(|| format!("There is no icon named {} in the {} set", name, self.prefix))?;
+        let parent = self.resolve(&alias.parent, depth + 1)?;
+        Ok(ResolvedIcon {
+            body: parent.body,
+            left: alias.left.unwrap_or(parent.left),
+            top: alias.top.unwrap_or(parent.top),
+            width: alias.width.unwrap_or(parent.width),
+            height: alias.height.unwrap_or(parent.height),
+            rotate: (parent.rotate + alias.rotate).rem_euclid(4),
+            h_flip: parent.h_flip != alias.h_flip,
+            v_flip: parent.v_flip != alias.v_flip,
+        })
+    }
+
+    /// A few icon names containing the text, to help with a typo
+    pub fn similar_names(&self, text: &str) -> Vec<String> {
+        let text = text.to_lowercase();
+        self.icons
+            .keys()
+            .chain(self.aliases.keys())
+            .filter(|name| name.contains(&text))
+            .take(5)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Writes a number the way JavaScript does, without a trailing .0
+fn number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small set whose icons are wider than tall, so a quarter turn shows in the size
+    fn icon_set() -> IconSet {
+        serde_json::from_str(
+            r#"{
+                "prefix": "test",
+                "width": 24,
+                "height": 16,
+                "icons": {
+                    "arrow": { "body": "<path d=\"M0 0h24\"/>" },
+                    "tall": { "body": "<path d=\"M0 0v32\"/>", "height": 32 },
+                    "secret": { "body": "<path/>", "hidden": true }
+                },
+                "aliases": {
+                    "arrow-down": { "parent": "arrow", "rotate": 1 },
+                    "arrow-back": { "parent": "arrow-down", "rotate": 3 },
+                    "arrow-mirrored": { "parent": "arrow", "hFlip": true },
+                    "arrow-mirrored-down": { "parent": "arrow-mirrored", "rotate": 1 },
+                    "arrow-upside-down": { "parent": "arrow-mirrored", "vFlip": true },
+                    "arrow-unflipped": { "parent": "arrow-mirrored", "hFlip": true },
+                    "arrow-wide": { "parent": "arrow", "width": 32 },
+                    "loop-a": { "parent": "loop-b" },
+                    "loop-b": { "parent": "loop-a" }
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    /// The svg render gives for a size, a viewBox and a body
+    fn svg(width: u32, height: u32, body: &str) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">{}</svg>",
+            width, height, width, height, body
+        )
+    }
+
+    #[test]
+    fn renders_an_icon_with_the_size_of_the_set() {
+        let set = icon_set();
+        assert_eq!(set.render("arrow").unwrap(), svg(24, 16, "<path d=\"M0 0h24\"/>"));
+        assert_eq!(set.render("tall").unwrap(), svg(24, 32, "<path d=\"M0 0v32\"/>"));
+    }
+
+    #[test]
+    fn turns_a_quarter_around_the_center_of_the_height() {
+        assert_eq!(
+            icon_set().render("arrow-down").unwrap(),
+            svg(16, 24, "<g transform=\"rotate(90 8 8)\"><path d=\"M0 0h24\"/></g>")
+        );
+    }
+
+    #[test]
+    fn adds_up_the_rotations_of_the_aliases() {
+        assert_eq!(icon_set().render("arrow-back").unwrap(), svg(24, 16, "<path d=\"M0 0h24\"/>"));
+    }
+
+    #[test]
+    fn flips_before_rotating() {
+        let set = icon_set();
+        assert_eq!(
+            set.render("arrow-mirrored").unwrap(),
+            svg(24, 16, "<g transform=\"translate(24 0) scale(-1 1)\"><path d=\"M0 0h24\"/></g>")
+        );
+        assert_eq!(
+            set.render("arrow-mirrored-down").unwrap(),
+            svg(16, 24, "<g transform=\"rotate(90 8 8) translate(24 0) scale(-1 1)\"><path d=\"M0 0h24\"/></g>")
+        );
+    }
+
+    #[test]
+    fn combines_the_flips_of_the_aliases() {
+        let set = icon_set();
+        // Both flips make a half turn
+        assert_eq!(
+            set.render("arrow-upside-down").unwrap(),
+            svg(24, 16, "<g transform=\"rotate(180 12 8)\"><path d=\"M0 0h24\"/></g>")
+        );
+        // Flipping twice the same way cancels out
+        assert_eq!(set.render("arrow-unflipped").unwrap(), svg(24, 16, "<path d=\"M0 0h24\"/>"));
+    }
+
+    #[test]
+    fn aliases_override_the_size() {
+        assert_eq!(icon_set().render("arrow-wide").unwrap(), svg(32, 16, "<path d=\"M0 0h24\"/>"));
+    }
+
+    #[test]
+    fn stops_at_alias_loops_and_unknown_names() {
+        let set = icon_set();
+        assert!(set.render("loop-a").unwrap_err().to_string().contains("points at itself"));
+        assert!(set.render("mirrored").unwrap_err().to_string().contains("did you mean arrow-mirrored, arrow-mirrored-down?"));
+    }
+
+    #[test]
+    fn lists_the_icons_that_are_not_hidden() {
+        assert_eq!(icon_set().icon_names(), vec!["arrow", "tall"]);
+    }
+}
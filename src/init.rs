@@ -0,0 +1,92 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use dialoguer::{console::style, Confirm, Input, Select};
+
+use crate::{
+    default::{Config, CONFIG_FILE_NAME},
+    parser::size_parser,
+    project::{detect_project, Framework},
+};
+
+/// Asks a few questions about the project and writes a complete quickicon.json in the current folder
+pub fn init() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let root = Path::new(".");
+    let config_file_path = root.join(CONFIG_FILE_NAME);
+    let project = detect_project(root);
+    let defaults = Config::default();
+
+    if config_file_path.exists() {
+        let overwrite = Confirm::new()
+            .with_prompt(format!("{} already exists, overwrite it?", config_file_path.display()))
+            .default(false)
+            .interact()?;
+        if !overwrite {
+            return Err("Nothing was written, the existing config was kept".into());
+        }
+    }
+
+    // The components are React ones whatever the project uses, so the framework is only checked, not asked
+    if let Some(framework) = project.framework.filter(|framework| *framework != Framework::React) {
+        println!(
+            "{}",
+            style(format!("⚠️  This looks like a {} project but QuickIcon only generates React components for now", framework)).yellow()
+        );
+        let proceed = Confirm::new()
+            .with_prompt("Set up React components anyway?")
+            .default(false)
+            .interact()?;
+        if !proceed {
+            return Err("Nothing was written".into());
+        }
+    }
+
+    let languages = ["TypeScript", "JavaScript"];
+    let language_index = Select::new()
+        .with_prompt("Which language should the components use?")
+        .items(languages)
+        .default(if project.has_typescript { 0 } else { 1 })
+        .interact()?;
+
+    let suggested_destination = project
+        .icons_folder
+        .unwrap_or(defaults.destination_folder);
+    let destination_folder: String = Input::new()
+        .with_prompt("Where should the icons be saved?")
+        .default(suggested_destination.to_string_lossy().to_string())
+        .interact_text()?;
+
+    let size: String = Input::new()
+        .with_prompt("Default size of the icons, in pixels")
+        .default(defaults.size.to_string())
+        .validate_with(|input: &String| size_parser(input).map(|_| ()))
+        .interact_text()?;
+
+    let color_strategies = [
+        "currentColor, the icons take the color of the surrounding text",
+        "A fixed default color",
+    ];
+    let color_index = Select::new()
+        .with_prompt("How should the icons be colored?")
+        .items(color_strategies)
+        .default(0)
+        .interact()?;
+    let color = if color_index == 0 {
+        "currentColor".to_string()
+    } else {
+        Input::new()
+            .with_prompt("Default color")
+            .default(defaults.color)
+            .interact_text()?
+    };
+
+    let config = Config {
+        is_javascript: language_index == 1,
+        destination_folder: PathBuf::from(destination_folder),
+        size: size_parser(&size)?,
+        color,
+        ..Config::default()
+    };
+
+    fs::write(&config_file_path, serde_json::to_string_pretty(&config)? + "\n")?;
+    Ok(config_file_path)
+}
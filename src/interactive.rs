@@ -0,0 +1,200 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use dialoguer::{console::{style, Term}, Confirm, Input, Select};
+
+use crate::{
+    content::{choose_page_svg, fetch_svgs, get_source_content, page_svg_source, read_clipboard_svg, read_sprite, Source},
+    default::Config,
+    iconify,
+    parser::{component_name_parser, extract_title, to_pascal_case},
+};
+
+/// How many lines of the svg the preview shows
+static PREVIEW_LINES: usize = 6;
+
+/// What the interactive flow settled on
+pub struct Conversion {
+    pub icon_name: String,
+    pub source: Source,
+    pub content: String,
+    pub destination_folder: PathBuf,
+}
+
+/// Walks through picking the svg, naming the component and confirming the destination.
+/// The svg comes from --path when given, else the clipboard or one of the svg files in the current folder.
+pub async fn prompt_conversion(source: Option<Source>, config: &Config) -> Result<Conversion, Box<dyn std::error::Error>> {
+    if !Term::stdout().is_term() {
+        return Err("--icon-name is required when QuickIcon is not running in a terminal".into());
+    }
+
+    let mut source = match source {
+        Some(source) => source,
+        None => pick_source()?,
+    };
+    let content = read_source(&mut source).await?;
+    print_preview(&content);
+
+    let icon_name: String = Input::new()
+        .with_prompt("Component name")
+        .default(suggest_name(&source, &content, &config.name_suffix))
+        .validate_with(|input: &String| component_name_parser(input, &config.name_suffix).map(|_| ()))
+        .interact_text()?;
+    let component_name = component_name_parser(&icon_name, &config.name_suffix)?;
+    if let Some(explanation) = component_name.explain() {
+        println!("{}", style(explanation).yellow());
+    }
+    let icon_name = component_name.name;
+
+    let destination_folder: String = Input::new()
+        .with_prompt("Destination folder")
+        .default(config.destination_folder.to_string_lossy().to_string())
+        .interact_text()?;
+
+    let proceed = Confirm::new()
+        .with_prompt(format!("Generate {} in {}?", icon_name, destination_folder))
+        .default(true)
+        .interact()?;
+    if !proceed {
+        return Err("The conversion was cancelled".into());
+    }
+
+    Ok(Conversion {
+        icon_name,
+        source,
+        content,
+        destination_folder: PathBuf::from(destination_folder),
+    })
+}
+
+/// Reads the svg of the source, letting the user pick one when a page holds several and there is a terminal.
+/// The choice is kept in the source so the lockfile can find the same svg again.
+pub async fn read_source(source: &mut Source) -> Result<String, Box<dyn std::error::Error>> {
+    if let Source::Iconify { path, icon: None } = source {
+        if Term::stdout().is_term() {
+            let icon = pick_icon(path)?;
+            *source = Source::Iconify { path: path.clone(), icon: Some(icon) };
+        }
+        return get_source_content(source).await;
+    }
+    if let Source::Sprite { path, symbol: None } = source {
+        let symbols = read_sprite(path)?;
+        if symbols.len() > 1 && Term::stdout().is_term() {
+            let ids: Vec<&str> = symbols.iter().map(|symbol| symbol.id.as_str()).collect();
+            let index = Select::new()
+                .with_prompt(format!("The sprite holds {} symbols, which one do you want to convert?", symbols.len()))
+                .items(&ids)
+                .default(0)
+                .interact()?;
+            *source = Source::Sprite { path: path.clone(), symbol: Some(symbols[index].id.clone()) };
+        }
+        return get_source_content(source).await;
+    }
+    let Source::Url { url, select: None } = source else {
+        return get_source_content(source).await;
+    };
+    let svgs = fetch_svgs(url).await?;
+    if svgs.len() == 1 || !Term::stdout().is_term() {
+        return choose_page_svg(&svgs, None);
+    }
+
+    let labels: Vec<String> = svgs.iter().map(|found| found.summary()).collect();
+    let index = Select::new()
+        .with_prompt(format!("The page holds {} svgs, which one do you want to convert?", svgs.len()))
+        .items(&labels)
+        .default(0)
+        .interact()?;
+    *source = page_svg_source(url, &svgs, index);
+    Ok(svgs[index].svg.clone())
+}
+
+/// Asks for the name of an icon of the set, a list of the thousands of icons of a set being of little help
+fn pick_icon(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let set = iconify::load(Path::new(path))?;
+    let icon = Input::<String>::new()
+        .with_prompt(format!("Which icon of the {} set ({} icons)?", set.prefix, set.icon_names().len()))
+        .validate_with(|input: &String| {
+            if set.contains(input.trim()) {
+                return Ok(());
+            }
+            match set.similar_names(input.trim()).as_slice() {
+                [] => Err(format!("There is no icon named {}", input.trim())),
+                similar => Err(format!("There is no icon named {}, did you mean {}?", input.trim(), similar.join(", "))),
+            }
+        })
+        .interact_text()?;
+    Ok(icon.trim().to_string())
+}
+
+/// Offers the svg on the clipboard, if any, and every svg file in the current folder
+fn pick_source() -> Result<Source, Box<dyn std::error::Error>> {
+    let mut sources = Vec::new();
+    let mut labels = Vec::new();
+
+    if let Ok(svg) = read_clipboard_svg() {
+        labels.push(format!("Clipboard ({})", summarize(&svg)));
+        sources.push(Source::Clipboard);
+    }
+
+    let mut svg_files: Vec<PathBuf> = fs::read_dir(".")?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|extension| extension == "svg" || extension == "svgz").unwrap_or(false))
+        .collect();
+    svg_files.sort();
+    for file in svg_files {
+        labels.push(file.display().to_string());
+        sources.push(Source::from_path(Some(&file.to_string_lossy())));
+    }
+
+    match sources.len() {
+        0 => Err("There is no svg on the clipboard and no .svg or .svgz file in the current folder, pass one with --path".into()),
+        1 => Ok(sources.remove(0)),
+        _ => {
+            let index = Select::new()
+                .with_prompt("Which svg do you want to convert?")
+                .items(&labels)
+                .default(0)
+                .interact()?;
+            Ok(sources.remove(index))
+        }
+    }
+}
+
+/// Shows the start of the svg so it can be recognized before converting it
+fn print_preview(content: &str) {
+    println!("{}", style(format!("Found an svg, {}", summarize(content))).cyan());
+    let lines: Vec<&str> = content.trim().lines().collect();
+    for line in lines.iter().take(PREVIEW_LINES) {
+        println!("  {}", style(line).dim());
+    }
+    if lines.len() > PREVIEW_LINES {
+        println!("  {}", style(format!("... {} more lines", lines.len() - PREVIEW_LINES)).dim());
+    }
+}
+
+/// A one line description of an svg e.g 1.2 KB, "Heart"
+fn summarize(svg: &str) -> String {
+    let size = format!("{:.1} KB", svg.len() as f64 / 1024.0);
+    match extract_title(svg) {
+        Some(title) => format!("{}, \"{}\"", size, title),
+        None => size,
+    }
+}
+
+/// Suggests a component name from the file name, else the svg title, else Icon
+fn suggest_name(source: &Source, content: &str, suffix: &str) -> String {
+    let from_file = match source {
+        Source::File { path } => PathBuf::from(path)
+            .file_stem()
+            .map(|stem| to_pascal_case(&stem.to_string_lossy()))
+            .filter(|name| !name.is_empty()),
+        Source::Iconify { icon: Some(icon), .. } => Some(to_pascal_case(icon)),
+        Source::Sprite { symbol: Some(symbol), .. } => Some(to_pascal_case(symbol)),
+        _ => None,
+    };
+
+    from_file
+        .or_else(|| extract_title(content).map(|title| to_pascal_case(&title)))
+        .and_then(|name| component_name_parser(&name, suffix).ok())
+        .map(|component_name| component_name.name)
+        .unwrap_or_else(|| "Icon".to_string())
+}
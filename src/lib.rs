@@ -13,13 +13,18 @@ pub async fn convert_svg_to_react(
     use std::path::PathBuf;
     use crate::convert::SvgToReact;
     use crate::default::Config;
+    use crate::parser::component_name_parser;
 
     let config = Config {
         is_javascript,
         destination_folder: PathBuf::from(destination_folder),
         size,
+        ..Config::default()
     };
 
+    let component_name = component_name_parser(&component_name, &config.name_suffix)
+        .map_err(napi::Error::from_reason)?
+        .name;
     let converter = SvgToReact::new(svg_content, component_name, config);
     
     match converter.convert_and_save() {
@@ -39,7 +44,22 @@ pub fn validate_svg(content: String) -> bool {
 // Re-export modules for binary usage
 pub mod args;
 pub mod parser;
+pub mod project;
 pub mod asset;
+pub mod batch;
+pub mod cache;
+pub mod check;
+pub mod clipboard;
 pub mod content;
 pub mod convert;
-pub mod default;
\ No newline at end of file
+pub mod default;
+pub mod fetch;
+pub mod hash;
+pub mod iconify;
+pub mod init;
+pub mod interactive;
+pub mod lockfile;
+pub mod raster;
+pub mod sprite;
+pub mod verify;
+pub mod watch;
\ No newline at end of file
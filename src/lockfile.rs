@@ -0,0 +1,199 @@
+use std::{collections::BTreeMap, fs, path::{Component, Path, PathBuf}};
+
+use dialoguer::console::style;
+use serde::{Serialize, Deserialize};
+
+use crate::{content::{get_source_content, Source}, convert::SvgToReact, default::{relative_path, Config}, hash::sha256_hex};
+
+/// The version of QuickIcon written into every lock entry
+pub static TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Everything needed to generate one component again
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockEntry {
+    pub component: String,
+    pub source: Source,
+    pub hash: String,
+    pub config: Config,
+    pub version: String,
+}
+
+/// The generated components of a project, keyed by the path of the component file.
+/// Every path in it is relative to the project root so the lockfile works from any folder.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Lockfile {
+    pub icons: BTreeMap<String, LockEntry>,
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+impl Lockfile {
+    /// Loads the lockfile of the project, an empty one is returned when it does not exist yet
+    pub fn load(root: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = lockfile_path(root);
+        let mut lockfile: Lockfile = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|err| format!("{} is not a valid lockfile: {}", path.display(), err))?
+        } else {
+            Lockfile::default()
+        };
+        lockfile.root = root.to_path_buf();
+        Ok(lockfile)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let json_lockfile = serde_json::to_string_pretty(self)?;
+        fs::write(lockfile_path(&self.root), json_lockfile + "\n")?;
+        Ok(())
+    }
+
+    /// The path of the lockfile on disk
+    pub fn path(&self) -> PathBuf {
+        lockfile_path(&self.root)
+    }
+
+    /// Turns a path stored in the lockfile into one usable from the current folder
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        // Stored paths start with ./, which would pile up on a root of . into ././icons/Icon.tsx
+        let path: PathBuf = Path::new(path).components().filter(|component| *component != Component::CurDir).collect();
+        self.root.join(path)
+    }
+
+    /// Adds or replaces the entry of the component saved at the output path.
+    /// The output, the source file, sprite or icon set and the destination folder are given relative to the current folder.
+    pub fn record(&mut self, output: &Path, component: &str, source: Source, svg: &str, config: &Config) {
+        let source = match source {
+            Source::File { path } => Source::File { path: self.relative(Path::new(&path)) },
+            Source::Sprite { path, symbol } => Source::Sprite { path: self.relative(Path::new(&path)), symbol },
+            Source::Iconify { path, icon } => Source::Iconify { path: self.relative(Path::new(&path)), icon },
+            source => source,
+        };
+        let mut config = config.clone();
+        config.destination_folder = PathBuf::from(self.relative(&config.destination_folder));
+
+        self.icons.insert(
+            self.relative(output),
+            LockEntry {
+                component: component.to_string(),
+                source,
+                hash: hash_svg(svg),
+                config,
+                version: TOOL_VERSION.to_string(),
+            },
+        );
+    }
+
+    pub fn remove(&mut self, output: &Path) {
+        self.icons.remove(&self.relative(output));
+    }
+
+    /// Reads the source of an entry again and renders its component in memory.
+    /// Returns the svg that was read along with the component.
+    pub async fn render_entry(&self, entry: &LockEntry) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let source = match &entry.source {
+            Source::Clipboard => {
+                return Err("it was generated from the clipboard, which cannot be read again".into());
+            },
+            Source::File { path } => Source::File { path: self.resolve(path).to_string_lossy().to_string() },
+            Source::Sprite { path, symbol } => Source::Sprite { path: self.resolve(path).to_string_lossy().to_string(), symbol: symbol.clone() },
+            Source::Iconify { path, icon } => Source::Iconify { path: self.resolve(path).to_string_lossy().to_string(), icon: icon.clone() },
+            source => source.clone(),
+        };
+        let svg = get_source_content(&source).await?;
+        let component = SvgToReact::new(svg.clone(), entry.component.clone(), entry.config.clone()).render_component()?;
+        Ok((svg, component))
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        relative_path(&self.root, path).to_string_lossy().to_string()
+    }
+}
+
+/// The path of the lockfile, which lives next to the config file
+pub fn lockfile_path(root: &Path) -> PathBuf {
+    root.join("quickicon.lock.json")
+}
+
+/// Records a freshly generated component in the project lockfile
+pub fn record_generated(root: &Path, output: &Path, component: &str, source: Source, svg: &str, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lockfile = Lockfile::load(root)?;
+    lockfile.record(output, component, source, svg, config);
+    lockfile.save()
+}
+
+/// Removes a component from the project lockfile
+pub fn remove_generated(root: &Path, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lockfile = Lockfile::load(root)?;
+    lockfile.remove(output);
+    lockfile.save()
+}
+
+/// The sha256 of an svg as a hex string
+pub fn hash_svg(svg: &str) -> String {
+    sha256_hex(svg)
+}
+
+/// Rebuilds every component in the lockfile and reports which ones changed
+pub async fn regenerate(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lockfile = Lockfile::load(root)?;
+    if lockfile.icons.is_empty() {
+        return Err(format!("There are no icons recorded in {}", lockfile.path().display()).into());
+    }
+
+    let (mut changed, mut unchanged, mut skipped) = (0, 0, 0);
+    let outputs: Vec<String> = lockfile.icons.keys().cloned().collect();
+
+    for output in outputs {
+        let entry = lockfile.icons[&output].clone();
+        let (svg, component) = match lockfile.render_entry(&entry).await {
+            Ok(rendered) => rendered,
+            Err(err) => {
+                skipped += 1;
+                println!("{} {} was skipped because {}", style("⚠️").yellow(), output, err);
+                continue;
+            }
+        };
+
+        let output_path = lockfile.resolve(&output);
+        if fs::read_to_string(&output_path).ok().as_deref() == Some(component.as_str()) {
+            unchanged += 1;
+            println!("   {} is unchanged", output);
+        } else {
+            if let Some(folder) = output_path.parent() {
+                fs::create_dir_all(folder)?;
+            }
+            fs::write(&output_path, &component)?;
+            changed += 1;
+            println!("{}", style(format!("🔁 {} changed", output)).green());
+        }
+        lockfile.icons.insert(output, LockEntry {
+            hash: hash_svg(&svg),
+            version: TOOL_VERSION.to_string(),
+            ..entry
+        });
+    }
+
+    lockfile.save()?;
+    println!(
+        "{}",
+        style(format!("{} changed, {} unchanged, {} skipped", changed, unchanged, skipped)).bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_stored_paths_without_doubling_the_current_folder() {
+        // Compared as text, as paths that differ by a . in the middle are equal
+        let lockfile = Lockfile { root: PathBuf::from("."), ..Lockfile::default() };
+        assert_eq!(lockfile.resolve("./icons/HeartIcon.tsx").display().to_string(), "./icons/HeartIcon.tsx");
+        assert_eq!(lockfile.resolve("icons/HeartIcon.tsx").display().to_string(), "./icons/HeartIcon.tsx");
+
+        let lockfile = Lockfile { root: PathBuf::from("../app"), ..Lockfile::default() };
+        assert_eq!(lockfile.resolve("./icons/HeartIcon.tsx").display().to_string(), "../app/icons/HeartIcon.tsx");
+        assert_eq!(lockfile.resolve("../shared/star.svg").display().to_string(), "../app/../shared/star.svg");
+    }
+}
@@ -1,32 +1,265 @@
+use std::{path::PathBuf, time::Duration};
+
 use clap::Parser;
 use dialoguer::console::style;
 use asset::{QUICK_ICON};
-use args::Args;
+use args::{Args, CacheAction, Command, ConfigAction};
 
-use crate::{convert::SvgToReact};
+use crate::{content::Source, convert::SvgToReact};
 
 mod args;
 mod parser;
+mod project;
 mod asset;
+mod batch;
+mod cache;
+mod check;
+mod clipboard;
 mod content;
 mod convert;
 mod default;
+mod fetch;
+mod hash;
+mod iconify;
+mod init;
+mod interactive;
+mod lockfile;
+mod raster;
+mod sprite;
+mod verify;
+mod watch;
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    fetch::set_offline(args.offline);
     println!("{}", style(QUICK_ICON).blue());
-    let config = default::get_and_save_config(&args).unwrap();
-
-    match content::get_content(&args).await {
-        Ok(content) => {            
-            match SvgToReact::new(content, args.icon_name, config).convert_and_save() {
-                Ok(path) => {
-                    let msg = style(format!("🎉 Your icon has been generated and you can find it in: {:?}", path)).green();
-                    println!("{}", msg);
+    // init writes the config, so it runs before any config is loaded
+    if args.command == Some(Command::Init) {
+        match init::init() {
+            Ok(path) => println!("{}", style(format!("🎉 Your config has been written to {}", path.display())).green()),
+            Err(err) => println!("An error occurred while setting up the config: {}", style(err).red()),
+        }
+        return;
+    }
+
+    let (mut config, origins) = match default::get_and_save_config(&args) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            println!("An error occurred while loading the config: {}", style(err).red());
+            std::process::exit(1);
+        }
+    };
+    let root = default::project_root(&args);
+
+    if let Some(command) = &args.command {
+        match command {
+            Command::Init => {},
+            Command::WatchClipboard { auto_name, interval } => {
+                match watch::watch_clipboard(&root, config, *auto_name, *interval).await {
+                    Ok(generated) => {
+                        println!("{}", style(format!("👋 Stopped watching the clipboard, {} icon(s) generated", generated.len())).green());
+                    },
+                    Err(err) => {
+                        println!("An error occurred while watching the clipboard: {}", style(err).red());
+                    }
+                }
+            },
+            Command::Watch { source, debounce } => {
+                if let Err(err) = watch::watch_directory(&root, &PathBuf::from(source), config, *debounce) {
+                    println!("An error occurred while watching the folder: {}", style(err).red());
+                }
+            },
+            Command::Regenerate => {
+                if let Err(err) = lockfile::regenerate(&root).await {
+                    println!("An error occurred while regenerating the icons: {}", style(err).red());
+                }
+            },
+            Command::Config { action: ConfigAction::Show } => {
+                default::show_config(&args, &config, &origins);
+            },
+            Command::Cache { action: CacheAction::List } => {
+                if let Err(err) = cache::show_cache() {
+                    println!("An error occurred while reading the cache: {}", style(err).red());
+                }
+            },
+            Command::Cache { action: CacheAction::Prune { older_than } } => {
+                let older_than = older_than.map(|days| Duration::from_secs(days * 86400));
+                match cache::prune(older_than) {
+                    Ok(removed) => {
+                        for entry in &removed {
+                            println!("   {}", entry.url);
+                        }
+                        println!("{}", style(format!("🧹 {} cached response(s) removed", removed.len())).green());
+                    },
+                    Err(err) => println!("An error occurred while pruning the cache: {}", style(err).red()),
+                }
+            },
+            Command::Check { source } => {
+                let source = source.as_ref().map(PathBuf::from);
+                match check::check(&root, source.as_deref(), &config).await {
+                    Ok(mismatches) if mismatches.is_empty() => {
+                        println!("{}", style("✅ Every icon is up to date").green());
+                    },
+                    Ok(mismatches) => {
+                        println!("{}", style(format!("❌ {} icon(s) are out of date:", mismatches.len())).red());
+                        for mismatch in mismatches {
+                            println!("   {}: {}", mismatch.path.display(), mismatch.reason);
+                        }
+                        std::process::exit(1);
+                    },
+                    Err(err) => {
+                        println!("An error occurred while checking the icons: {}", style(err).red());
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        return;
+    }
+
+    let source = match Source::from_path(args.path.as_deref()).with_select(args.select.clone()) {
+        Ok(source) => source,
+        Err(err) => {
+            println!("{}", style(err).red());
+            std::process::exit(1);
+        }
+    };
+
+    let raster_options = (args.png.is_some() || args.ico).then(|| {
+        let mut sizes = args.png.clone().unwrap_or_else(|| raster::DEFAULT_ICO_SIZES.to_vec());
+        sizes.sort();
+        sizes.dedup();
+        raster::RasterOptions { sizes, png: args.png.is_some(), ico: args.ico, color: args.color.clone() }
+    });
+
+    if args.all {
+        let items = match &source {
+            Source::Url { url, .. } => batch::page_items(url, args.icon_name.as_deref().unwrap_or("Icon"), &config).await,
+            Source::Sprite { path, .. } => batch::sprite_items(path, &config),
+            Source::Iconify { path, .. } => batch::icon_set_items(path, &config),
+            _ => Err("--all only works when --path is a url, a sprite or an Iconify icon set".into()),
+        };
+        let generated = items.and_then(|items| {
+            if let Some(options) = &raster_options {
+                let total = items.len();
+                if batch::generate_raster(items, options, &config) < total {
+                    std::process::exit(1);
+                }
+                Ok(())
+            } else if args.sprite {
+                batch::generate_sheet(items, &config).map(|_| ())
+            } else {
+                let total = items.len();
+                let generated = batch::generate_all(&root, items, &config, args.verify);
+                if args.verify.is_some() && generated.len() < total {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+        });
+        if let Err(err) = generated {
+            println!("An error occurred while reading content: {}", style(err).red());
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let icon_name = match args.icon_name.as_deref().map(|name| parser::component_name_parser(name, &config.name_suffix)) {
+        Some(Ok(component_name)) => {
+            if let Some(explanation) = component_name.explain() {
+                println!("{}", style(explanation).yellow());
+            }
+            Some(component_name.name)
+        },
+        Some(Err(err)) => {
+            println!("{}", style(err).red());
+            std::process::exit(1);
+        },
+        None => None,
+    };
+
+    let conversion = match icon_name {
+        Some(icon_name) => {
+            let mut source = source;
+            interactive::read_source(&mut source)
+                .await
+                .map(|content| (icon_name, source, content))
+        },
+        None => interactive::prompt_conversion(args.path.is_some().then_some(source), &config)
+            .await
+            .map(|conversion| {
+                config.destination_folder = conversion.destination_folder;
+                (conversion.icon_name, conversion.source, conversion.content)
+            }),
+    };
+
+    match conversion {
+        Ok((icon_name, source, content)) => {
+            if let Some(options) = &raster_options {
+                if batch::generate_raster(vec![batch::BatchItem { name: icon_name, source, svg: content }], options, &config) == 0 {
+                    std::process::exit(1);
+                }
+                return;
+            }
+            if args.sprite {
+                if let Err(err) = batch::generate_sheet(vec![batch::BatchItem { name: icon_name, source, svg: content }], &config) {
+                    println!("An error occurred when adding the icon to the sprite sheet: {}", style(err).red());
+                }
+                return;
+            }
+
+            if let Some(threshold) = args.verify {
+                match verify::verify(&icon_name, &content, &config, threshold) {
+                    Ok(verification) if verification.passed() => {
+                        println!("{}", style(format!("🔍 The component renders like the svg: {}", verification.summary())).green());
+                    },
+                    Ok(verification) => {
+                        println!("{}", style(format!("❌ The component does not render like the svg: {}", verification.summary())).red());
+                        std::process::exit(1);
+                    },
+                    Err(err) => {
+                        println!("An error occurred while verifying the component: {}", style(err).red());
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            let converter = SvgToReact::new(content.clone(), icon_name.clone(), config.clone());
+            match args.copy.as_deref() {
+                Some(what) => {
+                    let rendered = if what == "jsx" {
+                        converter.render_jsx()
+                    } else {
+                        converter.render_component()
+                    };
+                    match rendered.and_then(|source| Ok(clipboard::write_text(&source)?)) {
+                        Ok(()) => {
+                            let msg = style(format!("📋 Your {} has been copied to the clipboard", what)).green();
+                            println!("{}", msg);
+                        },
+                        Err(err) => {
+                            println!("An error occurred when copying the component: {}", style(err).red());
+                            std::process::exit(1);
+                        }
+                    }
                 },
-                Err(err) => {
-                    println!("An error occurred when generating the component: {}", style(err).red());
+                None => {
+                    match converter.convert_and_save() {
+                        Ok(path) => {
+                            let msg = style(format!("🎉 Your icon has been generated and you can find it in: {:?}", path)).green();
+                            println!("{}", msg);
+                            if let Some(import) = project::detect_project(&root).alias_import(&root, &path) {
+                                println!("{}", style(format!("Import it with: import {} from '{}'", icon_name, import)).dim());
+                            }
+                            if let Err(err) = lockfile::record_generated(&root, &path, &icon_name, source, &content, &config) {
+                                println!("The icon could not be recorded in the lockfile: {}", style(err).yellow());
+                            }
+                        },
+                        Err(err) => {
+                            println!("An error occurred when generating the component: {}", style(err).red());
+                        }
+                    }
                 }
             }
         },
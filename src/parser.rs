@@ -1,25 +1,9 @@
-use std::{fs, path::PathBuf};
-
 use regex::Regex;
 
 /// Parses the directory where the icon is to be stored
 pub fn directory_parser(s: &str) -> Result<String, String> {
-    match PathBuf::try_from(s) {
-        Ok(_path) => {
-            Ok(s.to_string())
-        },
-        Err(_) => {
-            let new_directory = fs::create_dir(s);
-            match new_directory {
-                Ok(_) => {
-                    return Ok(s.to_string());
-                },
-                Err(_) => {
-                    return Err("Directory does not exist and creation failed".to_string());
-                }
-            }
-        }
-    }
+    // Any text is a valid path, the folder is created when the icon is saved
+    Ok(s.to_string())
 }
 
 /// Checks if the size is actually a valid value
@@ -28,13 +12,13 @@ pub fn size_parser(s: &str) -> Result<u32, String> {
     match num {
         Ok(num) => {
             if num > 0 {
-                return Ok(num);
+                Ok(num)
             } else {
-                return Err("The size of the icon cannot be 0".to_string());
+                Err("The size of the icon cannot be 0".to_string())
             }
         },
         Err(_) => {
-            return Err("Please enter a valid number greater than 0 for the size".to_string())
+            Err("Please enter a valid number greater than 0 for the size".to_string())
         }
     }
 }
@@ -43,9 +27,288 @@ pub fn size_parser(s: &str) -> Result<u32, String> {
 pub fn svg_validator(s: &str) -> bool {
     let pattern = r#"(?s)<svg[^>]*>.*?</svg>"#;
     let re = Regex::new(pattern).unwrap();
-    let tag = re.captures(&s);
-    match tag {
-        None => false,
-        Some(_) => true
+    re.is_match(s)
+}
+
+/// Pulls the first svg element out of a larger document e.g a html page or fragment
+pub fn extract_svg(s: &str) -> Option<String> {
+    find_svgs(s).into_iter().next()
+}
+
+/// Finds every top level svg element of a document, the svgs nested in them staying part of their parent
+pub fn find_svgs(s: &str) -> Vec<String> {
+    find_elements(s, Some("svg"))
+}
+
+/// Finds every element with the tag name that is not nested in another one with the same name,
+/// or every element not nested in another one at all without a tag name
+pub fn find_elements(s: &str, tag_name: Option<&str>) -> Vec<String> {
+    let tag_name = tag_name.map(regex::escape).unwrap_or_else(|| r"[a-zA-Z][\w:.-]*".to_string());
+    let pattern = format!(r#"(?i)<(/?){}\b[^>]*?(/?)>"#, tag_name);
+    let re = Regex::new(&pattern).unwrap();
+
+    let mut elements = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for tag in re.captures_iter(s) {
+        let whole = tag.get(0).unwrap();
+        if !tag[1].is_empty() {
+            if depth > 0 {
+                depth -= 1;
+                if depth == 0 {
+                    elements.push(s[start..whole.end()].to_string());
+                }
+            }
+        } else if !tag[2].is_empty() {
+            if depth == 0 {
+                elements.push(whole.as_str().to_string());
+            }
+        } else {
+            if depth == 0 {
+                start = whole.start();
+            }
+            depth += 1;
+        }
+    }
+    elements
+}
+
+/// Finds the svg files a html page links to through <img src> or a CSS url(), in the order they appear
+pub fn find_svg_assets(html: &str) -> Vec<String> {
+    let patterns = [
+        r#"(?i)<img\b[^>]*?\ssrc\s*=\s*["']([^"']+?\.svg(?:[?#][^"']*)?)["']"#,
+        r#"(?i)url\(\s*["']?([^"')]+?\.svg(?:[?#][^"')]*)?)["']?\s*\)"#,
+    ];
+
+    let mut assets: Vec<(usize, String)> = Vec::new();
+    for pattern in patterns {
+        let re = Regex::new(pattern).unwrap();
+        for asset in re.captures_iter(html) {
+            let link = asset.get(1).unwrap();
+            if !link.as_str().starts_with("data:") {
+                assets.push((link.start(), link.as_str().trim().to_string()));
+            }
+        }
+    }
+    assets.sort();
+
+    let mut unique: Vec<String> = Vec::new();
+    for (_, asset) in assets {
+        if !unique.contains(&asset) {
+            unique.push(asset);
+        }
+    }
+    unique
+}
+
+/// Reads an attribute of the opening svg tag e.g the id or the viewBox
+pub fn svg_attribute(svg: &str, name: &str) -> Option<String> {
+    let opening_tag = Regex::new(r#"(?i)<svg\b[^>]*>"#).unwrap().find(svg)?;
+    tag_attribute(opening_tag.as_str(), name)
+}
+
+/// Reads an attribute of the first tag of a piece of markup e.g the id of a <symbol>
+pub fn tag_attribute(markup: &str, name: &str) -> Option<String> {
+    let opening_tag = Regex::new(r#"<[^>]*>"#).unwrap().find(markup)?;
+    let pattern = format!(r#"\s{}\s*=\s*(?:"([^"]*)"|'([^']*)')"#, regex::escape(name));
+    let re = Regex::new(&pattern).unwrap();
+    re.captures(opening_tag.as_str())
+        .and_then(|value| value.get(1).or(value.get(2)))
+        .map(|value| value.as_str().to_string())
+}
+
+/// Reads the text of the <title> of an svg
+pub fn extract_title(s: &str) -> Option<String> {
+    let pattern = r#"(?s)<title[^>]*>(.*?)</title>"#;
+    let re = Regex::new(pattern).unwrap();
+    re.captures(s)
+        .map(|title| title[1].trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// Turns a file name like heart-outline or arrow_left into a PascalCase name like HeartOutline
+pub fn to_pascal_case(s: &str) -> String {
+    s
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first_character) => {
+                    first_character.to_uppercase().chain(chars).collect()
+                }
+            }
+        })
+        .collect::<String>()
+}
+
+/// Parses the share of differing pixels --verify accepts, in percent
+pub fn threshold_parser(s: &str) -> Result<f64, String> {
+    match s.trim().trim_end_matches('%').parse::<f64>() {
+        Ok(threshold) if (0.0..=100.0).contains(&threshold) => Ok(threshold),
+        _ => Err("Please enter a percentage between 0 and 100 for the threshold".to_string()),
+    }
+}
+
+/// Turns a PascalCase name into kebab-case e.g ArrowLeft into arrow-left, SVGLogo into svg-logo
+pub fn to_kebab_case(s: &str) -> String {
+    let characters: Vec<char> = s.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let mut result = String::new();
+    for (index, character) in characters.iter().enumerate() {
+        if character.is_ascii_uppercase() && index > 0 {
+            let previous = characters[index - 1];
+            let next_is_lower = characters.get(index + 1).map(|next| next.is_ascii_lowercase()).unwrap_or(false);
+            if previous.is_ascii_lowercase() || previous.is_ascii_digit() || (previous.is_ascii_uppercase() && next_is_lower) {
+                result.push('-');
+            }
+        }
+        result.push(character.to_ascii_lowercase());
+    }
+    result
+}
+
+/// The words a component cannot be named after: the JavaScript reserved words and React and SVGProps, which the component imports
+static RESERVED_WORDS: [&str; 48] = [
+    "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do", "else",
+    "enum", "export", "extends", "false", "finally", "for", "function", "if", "implements", "import", "in",
+    "instanceof", "interface", "let", "new", "null", "package", "private", "protected", "public", "return", "static",
+    "super", "switch", "this", "throw", "true", "try", "typeof", "var", "void", "while", "with", "yield", "React",
+    "SVGProps",
+];
+
+/// A component name that is safe to use as a file name and a JavaScript identifier
+pub struct ComponentName {
+    pub name: String,
+    /// What was changed in the name, empty when it was used as given
+    pub changes: Vec<String>,
+}
+
+impl ComponentName {
+    /// Describes the changes made to the name, if any
+    pub fn explain(&self) -> Option<String> {
+        if self.changes.is_empty() {
+            return None;
+        }
+        Some(format!("The component is named {}: {}", self.name, self.changes.join(", ")))
+    }
+}
+
+/// Turns a name like eye-icon or my icon into a PascalCase identifier, adding the suffix when it is missing.
+/// Path separators, reserved words and leading digits are rejected rather than fixed.
+pub fn component_name_parser(s: &str, suffix: &str) -> Result<ComponentName, String> {
+    let trimmed = s.trim();
+    if trimmed.contains('/') || trimmed.contains('\\') || trimmed.contains("..") {
+        return Err(format!("The component name {:?} cannot contain path separators", s));
+    }
+    if RESERVED_WORDS.contains(&trimmed) {
+        return Err(format!("The component name {:?} is a reserved word in JavaScript", s));
+    }
+
+    let mut changes = Vec::new();
+    let mut name = to_pascal_case(trimmed);
+    if name.is_empty() {
+        return Err(format!("The component name {:?} has no letters or digits", s));
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!("The component name {:?} cannot start with a digit", s));
+    }
+    if name != trimmed {
+        changes.push(format!("converted {:?} to PascalCase", s));
+    }
+
+    if !suffix.is_empty() && !name.ends_with(suffix) {
+        name.push_str(suffix);
+        changes.push(format!("added the {} suffix", suffix));
+    }
+    if RESERVED_WORDS.contains(&name.as_str()) {
+        return Err(format!("The component name {:?} is a reserved word in JavaScript", name));
+    }
+    // The suffix comes from the config as it is, so the name is checked again once it is added
+    if !is_identifier_part(&name) {
+        return Err(format!("The component name {:?} is not a valid JavaScript identifier", name));
+    }
+
+    Ok(ComponentName { name, changes })
+}
+
+/// Checks that a name suffix or the end of an identifier only holds letters, digits, _ and $
+pub fn is_identifier_part(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// A one line description of an svg to tell it apart from the others on a page e.g 24×24, id=logo, class=icon, 1.2 KB
+pub fn svg_summary(svg: &str) -> String {
+    let size = match (svg_attribute(svg, "width"), svg_attribute(svg, "height")) {
+        (Some(width), Some(height)) => Some(format!("{}×{}", width, height)),
+        _ => svg_attribute(svg, "viewBox").and_then(|view_box| {
+            let values: Vec<&str> = view_box.split(|c: char| c.is_whitespace() || c == ',').filter(|value| !value.is_empty()).collect();
+            (values.len() == 4).then(|| format!("{}×{}", values[2], values[3]))
+        }),
+    };
+
+    let mut parts: Vec<String> = size.into_iter().collect();
+    if let Some(id) = svg_attribute(svg, "id") {
+        parts.push(format!("id={}", id));
+    }
+    if let Some(class) = svg_attribute(svg, "class") {
+        parts.push(format!("class={}", class));
+    }
+    parts.push(format!("{:.1} KB", svg.len() as f64 / 1024.0));
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_names_to_pascal_case() {
+        assert_eq!(to_pascal_case("heart-outline"), "HeartOutline");
+        assert_eq!(to_pascal_case("arrow_left"), "ArrowLeft");
+        assert_eq!(to_pascal_case("my icon"), "MyIcon");
+        assert_eq!(to_pascal_case("--"), "");
+    }
+
+    #[test]
+    fn converts_names_to_kebab_case() {
+        assert_eq!(to_kebab_case("ArrowLeft"), "arrow-left");
+        assert_eq!(to_kebab_case("SVGLogo"), "svg-logo");
+        assert_eq!(to_kebab_case("Icon2Fa"), "icon2-fa");
+        assert_eq!(to_kebab_case("Eye"), "eye");
+    }
+
+    #[test]
+    fn normalizes_component_names() {
+        let eye = component_name_parser("eye-icon", "").unwrap();
+        assert_eq!(eye.name, "EyeIcon");
+        assert!(eye.explain().is_some());
+
+        assert_eq!(component_name_parser("my icon", "").unwrap().name, "MyIcon");
+        assert!(component_name_parser("Heart", "").unwrap().explain().is_none());
+    }
+
+    #[test]
+    fn adds_the_suffix_once() {
+        assert_eq!(component_name_parser("eye", "Icon").unwrap().name, "EyeIcon");
+        assert_eq!(component_name_parser("eye-icon", "Icon").unwrap().name, "EyeIcon");
+    }
+
+    #[test]
+    fn rejects_names_that_are_not_identifiers() {
+        assert!(component_name_parser("123Icon", "").is_err());
+        assert!(component_name_parser("default", "").is_err());
+        assert!(component_name_parser("React", "").is_err());
+        assert!(component_name_parser("SVGProps", "").is_err());
+        assert!(component_name_parser("../../etc/foo", "").is_err());
+        assert!(component_name_parser("icons\\foo", "").is_err());
+        assert!(component_name_parser("!!", "").is_err());
+    }
+
+    #[test]
+    fn rejects_suffixes_that_are_not_identifiers() {
+        assert!(component_name_parser("eye", "-icon").is_err());
+        assert!(component_name_parser("eye", "/../../escaped").is_err());
+        assert_eq!(component_name_parser("eye", "_$").unwrap().name, "Eye_$");
     }
 }
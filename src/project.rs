@@ -0,0 +1,178 @@
+use std::{fmt, fs, path::{Path, PathBuf}};
+
+use serde_json::Value;
+
+use crate::default::relative_path;
+
+/// The folders icons usually live in, in order of preference
+pub static ICON_FOLDERS: [&str; 3] = ["src/components/icons", "src/icons", "components/icons"];
+
+/// The UI frameworks QuickIcon can spot in package.json
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framework {
+    React,
+    Vue,
+    Svelte,
+}
+
+impl fmt::Display for Framework {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Framework::React => write!(f, "React"),
+            Framework::Vue => write!(f, "Vue"),
+            Framework::Svelte => write!(f, "Svelte"),
+        }
+    }
+}
+
+/// A wildcard alias from the tsconfig "paths" e.g "@/*": ["./src/*"] gives the prefix @/ for the folder ./src
+#[derive(Debug)]
+pub struct PathAlias {
+    pub prefix: String,
+    pub folder: PathBuf,
+}
+
+/// What QuickIcon could tell about a project by looking at its files
+#[derive(Debug, Default)]
+pub struct ProjectInfo {
+    pub has_package: bool,
+    pub has_typescript: bool,
+    pub framework: Option<Framework>,
+    pub icons_folder: Option<PathBuf>,
+    pub path_aliases: Vec<PathAlias>,
+}
+
+impl ProjectInfo {
+    /// Checks if there is a JavaScript project to look at
+    pub fn is_detected(&self) -> bool {
+        self.has_package || self.has_typescript
+    }
+
+    /// The import path of a file in the folder through a tsconfig alias e.g @/components/icons/EyeIcon
+    pub fn alias_import(&self, root: &Path, file: &Path) -> Option<String> {
+        let file = relative_path(root, file);
+        self.path_aliases.iter().find_map(|alias| {
+            let rest = file.strip_prefix(relative_path(root, &alias.folder)).ok()?;
+            let rest: Vec<String> = rest
+                .with_extension("")
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+            Some(format!("{}{}", alias.prefix, rest.join("/")))
+        })
+    }
+}
+
+/// Looks at the project in the folder: tsconfig.json, the package.json dependencies and the existing icon folders
+pub fn detect_project(root: &Path) -> ProjectInfo {
+    let tsconfig = read_tsconfig(root);
+    ProjectInfo {
+        has_package: root.join("package.json").is_file(),
+        has_typescript: tsconfig.is_some(),
+        framework: detect_framework(root),
+        icons_folder: ICON_FOLDERS
+            .iter()
+            .map(|folder| root.join(folder))
+            .find(|folder| folder.is_dir()),
+        path_aliases: tsconfig.map(|tsconfig| path_aliases(root, &tsconfig)).unwrap_or_default(),
+    }
+}
+
+/// Reads tsconfig.json, which may hold comments and trailing commas
+fn read_tsconfig(root: &Path) -> Option<Value> {
+    let path = root.join("tsconfig.json");
+    if !path.is_file() {
+        return None;
+    }
+    let content = fs::read_to_string(path).ok()?;
+    // A tsconfig that cannot be parsed still means a TypeScript project
+    Some(serde_json::from_str(&strip_json_comments(&content)).unwrap_or(Value::Null))
+}
+
+/// The wildcard aliases of compilerOptions.paths, their folders resolved against compilerOptions.baseUrl
+fn path_aliases(root: &Path, tsconfig: &Value) -> Vec<PathAlias> {
+    let compiler_options = &tsconfig["compilerOptions"];
+    let base_url = root.join(compiler_options["baseUrl"].as_str().unwrap_or("."));
+    let Some(paths) = compiler_options["paths"].as_object() else {
+        return Vec::new();
+    };
+
+    paths
+        .iter()
+        .filter_map(|(alias, targets)| {
+            let prefix = alias.strip_suffix('*')?;
+            let target = targets.get(0)?.as_str()?.strip_suffix('*')?;
+            Some(PathAlias {
+                prefix: prefix.to_string(),
+                folder: relative_path(Path::new("."), &base_url.join(target)),
+            })
+        })
+        .collect()
+}
+
+/// Removes the comments and the trailing commas JSON with comments allows, leaving the strings untouched
+fn strip_json_comments(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    // A comma is only written once the next token shows it is not a trailing one
+    let mut pending_comma = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {},
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                while chars.next_if(|&next| next != '\n').is_some() {}
+            },
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            },
+            (',', _) => pending_comma = true,
+            _ if c.is_whitespace() => stripped.push(c),
+            _ => {
+                if pending_comma && c != '}' && c != ']' {
+                    stripped.push(',');
+                }
+                pending_comma = false;
+                in_string = c == '"';
+                stripped.push(c);
+            },
+        }
+    }
+
+    stripped
+}
+
+/// Finds the framework in the dependencies of package.json, React winning when several are there
+fn detect_framework(root: &Path) -> Option<Framework> {
+    let package: Value = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())?;
+
+    let has_dependency = |name: &str| {
+        ["dependencies", "devDependencies", "peerDependencies"]
+            .iter()
+            .any(|section| package.get(section).and_then(|dependencies| dependencies.get(name)).is_some())
+    };
+
+    [("react", Framework::React), ("vue", Framework::Vue), ("svelte", Framework::Svelte)]
+        .into_iter()
+        .find(|(name, _)| has_dependency(name))
+        .map(|(_, framework)| framework)
+}
@@ -0,0 +1,156 @@
+use std::{fs, path::PathBuf, sync::OnceLock};
+
+use regex::{Captures, Regex};
+use resvg::{tiny_skia::{Pixmap, Transform}, usvg};
+
+use crate::{default::Config, parser::to_kebab_case};
+
+/// The sizes written when --ico is given without --png, the usual ones of a favicon
+pub static DEFAULT_ICO_SIZES: [u32; 3] = [16, 32, 48];
+
+/// The largest image an .ico can hold, in pixels
+static MAX_ICO_SIZE: u32 = 256;
+
+/// What to rasterize the svg to
+#[derive(Debug, Clone)]
+pub struct RasterOptions {
+    /// The width and height of every image, in pixels
+    pub sizes: Vec<u32>,
+    /// Write a PNG for every size
+    pub png: bool,
+    /// Write a multi-resolution .ico of the sizes up to 256
+    pub ico: bool,
+    /// Paint the icon with this color instead of the colors of the svg
+    pub color: Option<String>,
+}
+
+/// Parses the svg with the fonts of the system loaded, once, for the icons that hold text
+fn usvg_options() -> &'static usvg::Options<'static> {
+    static OPTIONS: OnceLock<usvg::Options<'static>> = OnceLock::new();
+    OPTIONS.get_or_init(|| {
+        let mut options = usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+        options
+    })
+}
+
+/// Draws the svg into a square image of the size, scaled to fit and centered
+pub fn rasterize(svg: &str, size: u32) -> Result<Pixmap, Box<dyn std::error::Error>> {
+    // usvg only takes svgs in the svg namespace, which svgs copied out of a page often leave out
+    let svg = if svg.contains("xmlns=") {
+        svg.to_string()
+    } else {
+        Regex::new(r"(?i)<svg\b").unwrap().replace(svg, r#"<svg xmlns="http://www.w3.org/2000/svg""#).to_string()
+    };
+    let tree = usvg::Tree::from_str(&svg, usvg_options()).map_err(|err| format!("The svg could not be rendered: {}", err))?;
+
+    let mut pixmap = Pixmap::new(size, size).ok_or("The size of the image cannot be 0")?;
+    let view = tree.size();
+    let scale = size as f32 / view.width().max(view.height());
+    let transform = Transform::from_scale(scale, scale).post_translate(
+        (size as f32 - view.width() * scale) / 2.0,
+        (size as f32 - view.height() * scale) / 2.0,
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+/// Paints the icon with the color: the colors the components turn into the color prop and currentColor
+pub fn substitute_color(svg: &str, color: &str) -> String {
+    let re = Regex::new(r##"\b(fill|stroke)="(#[0-9A-Fa-f]{6}|#[0-9A-Fa-f]{3}|currentColor)""##).unwrap();
+    let painted = re.replace_all(svg, |found: &Captures| {
+        if found[2].eq_ignore_ascii_case("#fff") || found[2].eq_ignore_ascii_case("#ffffff") {
+            found[0].to_string()
+        } else {
+            format!(r#"{}="{}""#, &found[1], color)
+        }
+    });
+    // currentColor left in styles or inherited follows the color of the root
+    Regex::new(r"(?i)<svg\b")
+        .unwrap()
+        .replace(&painted, format!(r#"<svg color="{}""#, color))
+        .to_string()
+}
+
+/// Packs PNG images into a multi-resolution .ico, which stores 256 as 0
+pub fn encode_ico(images: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut ico = Vec::new();
+    ico.extend_from_slice(&0u16.to_le_bytes());
+    ico.extend_from_slice(&1u16.to_le_bytes());
+    ico.extend_from_slice(&(images.len() as u16).to_le_bytes());
+
+    let mut offset = 6 + 16 * images.len() as u32;
+    for (size, png) in images {
+        let dimension = if *size >= MAX_ICO_SIZE { 0 } else { *size as u8 };
+        ico.push(dimension);
+        ico.push(dimension);
+        ico.push(0);
+        ico.push(0);
+        ico.extend_from_slice(&1u16.to_le_bytes());
+        ico.extend_from_slice(&32u16.to_le_bytes());
+        ico.extend_from_slice(&(png.len() as u32).to_le_bytes());
+        ico.extend_from_slice(&offset.to_le_bytes());
+        offset += png.len() as u32;
+    }
+    for (_, png) in images {
+        ico.extend_from_slice(png);
+    }
+    ico
+}
+
+/// Writes the images of the svg to the destination folder, named after the icon in kebab-case
+/// e.g favicon-32.png and favicon.ico for Favicon
+pub fn export(name: &str, svg: &str, options: &RasterOptions, config: &Config) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let svg = match &options.color {
+        Some(color) => substitute_color(svg, color),
+        None => svg.to_string(),
+    };
+    let file_name = to_kebab_case(name);
+
+    let mut images = Vec::new();
+    for size in &options.sizes {
+        let png = rasterize(&svg, *size)?.encode_png()?;
+        images.push((*size, png));
+    }
+
+    fs::create_dir_all(&config.destination_folder)?;
+    let mut written = Vec::new();
+    if options.png {
+        for (size, png) in &images {
+            let path = config.destination_folder.join(format!("{}-{}.png", file_name, size));
+            fs::write(&path, png)?;
+            written.push(path);
+        }
+    }
+    if options.ico {
+        let ico_images: Vec<(u32, Vec<u8>)> = images.into_iter().filter(|(size, _)| *size <= MAX_ICO_SIZE).collect();
+        if ico_images.is_empty() {
+            return Err(format!("An .ico holds images of {} pixels at most, pass smaller sizes", MAX_ICO_SIZE).into());
+        }
+        let path = config.destination_folder.join(format!("{}.ico", file_name));
+        fs::write(&path, encode_ico(&ico_images))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_the_ico_header_and_directory() {
+        let small = vec![1, 2, 3];
+        let large = vec![4, 5, 6, 7, 8];
+        let ico = encode_ico(&[(16, small.clone()), (256, large.clone())]);
+
+        // Reserved, type 1 for icons and two images
+        assert_eq!(ico[..6], [0, 0, 1, 0, 2, 0]);
+        // 16×16, no palette, 1 plane, 32 bits, 3 bytes at 6 + 2 × 16 = 38
+        assert_eq!(ico[6..22], [16, 16, 0, 0, 1, 0, 32, 0, 3, 0, 0, 0, 38, 0, 0, 0]);
+        // 256 is stored as 0, its 5 bytes follow the 3 of the first image
+        assert_eq!(ico[22..38], [0, 0, 0, 0, 1, 0, 32, 0, 5, 0, 0, 0, 41, 0, 0, 0]);
+        assert_eq!(ico[38..41], small[..]);
+        assert_eq!(ico[41..], large[..]);
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, PathBuf},
+};
+
+use dialoguer::console::style;
+use regex::{Captures, Regex};
+
+use crate::{
+    convert::{is_generated, GENERATED_MARKER},
+    default::Config,
+    parser::{find_elements, find_svgs, tag_attribute, to_kebab_case},
+};
+
+/// The attributes of a <symbol> carried over to the standalone svg
+static CARRIED_ATTRIBUTES: [&str; 2] = ["viewBox", "preserveAspectRatio"];
+
+/// The name of the sprite sheet written to the destination folder
+pub static SHEET_FILE_NAME: &str = "sprite.svg";
+
+/// The name of the component drawing the icons of the sprite sheet
+pub static ICON_COMPONENT: &str = "Icon";
+
+/// The first line of every sprite sheet QuickIcon writes, so it only ever replaces its own
+static SHEET_MARKER: &str = "<!-- Generated by QuickIcon -->";
+
+/// One <symbol> of a sprite turned into a standalone svg
+pub struct Symbol {
+    pub id: String,
+    pub svg: String,
+}
+
+/// An element found in the sprite, along with its id and the ids it points at
+struct Element {
+    id: String,
+    markup: String,
+    references: Vec<String>,
+}
+
+/// Checks if an svg is a sprite sheet: it holds symbols and uses none of them itself
+pub fn is_sprite(svg: &str) -> bool {
+    let outside_symbols = outside_symbols(svg);
+    outside_symbols.len() < svg.len() && !Regex::new(r"(?i)<use\b").unwrap().is_match(&outside_symbols)
+}
+
+/// The markup of the sprite with the symbols taken out
+fn outside_symbols(sprite: &str) -> String {
+    find_elements(sprite, Some("symbol"))
+        .iter()
+        .fold(sprite.to_string(), |outside, symbol| outside.replacen(symbol.as_str(), "", 1))
+}
+
+/// Turns every <symbol> with an id into a standalone svg, copying in the <defs> it references
+pub fn split_sprite(sprite: &str) -> Vec<Symbol> {
+    let symbols = find_symbols(sprite);
+    // The defs inside a symbol travel with it, only the ones of the sheet are shared
+    let shared: Vec<Element> = Regex::new(r"(?is)<defs\b[^>]*>(.*?)</defs>")
+        .unwrap()
+        .captures_iter(&outside_symbols(sprite))
+        .flat_map(|defs| find_elements(&defs[1], None))
+        .filter_map(|markup| {
+            let id = tag_attribute(&markup, "id")?;
+            Some(Element { id, references: references(&markup), markup })
+        })
+        .chain(symbols.iter().map(|symbol| Element {
+            id: symbol.id.clone(),
+            markup: symbol.markup.clone(),
+            references: symbol.references.clone(),
+        }))
+        .collect();
+
+    symbols
+        .iter()
+        .map(|symbol| {
+            let needed = needed_definitions(symbol, &shared);
+            let defs: String = shared
+                .iter()
+                .filter(|element| needed.contains(&element.id))
+                .map(|element| element.markup.as_str())
+                .collect();
+
+            let mut attributes = String::from(r#" xmlns="http://www.w3.org/2000/svg""#);
+            if symbol.markup.contains("xlink:") || defs.contains("xlink:") {
+                attributes.push_str(r#" xmlns:xlink="http://www.w3.org/1999/xlink""#);
+            }
+            // A symbol is sized by the <use> drawing it, the size prop of the component needs a width and height to replace
+            if let Some((width, height)) = tag_attribute(&symbol.markup, "viewBox").and_then(|view_box| view_box_size(&view_box)) {
+                attributes.push_str(&format!(r#" width="{}" height="{}""#, width, height));
+            }
+            for name in CARRIED_ATTRIBUTES {
+                if let Some(value) = tag_attribute(&symbol.markup, name) {
+                    attributes.push_str(&format!(r#" {}="{}""#, name, value));
+                }
+            }
+
+            let body = inner_markup(&symbol.markup);
+            let svg = if defs.is_empty() {
+                format!("<svg{}>{}</svg>", attributes, body)
+            } else {
+                format!("<svg{}><defs>{}</defs>{}</svg>", attributes, defs, body)
+            };
+            Symbol { id: symbol.id.clone(), svg }
+        })
+        .collect()
+}
+
+/// The width and height of a viewBox e.g 24 and 24 for "0 0 24 24", rounded to whole pixels
+fn view_box_size(view_box: &str) -> Option<(u32, u32)> {
+    let numbers: Vec<f64> = view_box
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|number| !number.is_empty())
+        .map(|number| number.parse().ok())
+        .collect::<Option<_>>()?;
+    match numbers[..] {
+        [_, _, width, height] if width > 0.0 && height > 0.0 => Some((width.round().max(1.0) as u32, height.round().max(1.0) as u32)),
+        _ => None,
+    }
+}
+
+/// The ids of the shared elements a symbol needs, following references between them e.g a gradient based on another
+fn needed_definitions(symbol: &Element, shared: &[Element]) -> Vec<String> {
+    let mut needed: Vec<String> = Vec::new();
+    let mut pending = symbol.references.clone();
+    while let Some(id) = pending.pop() {
+        if id == symbol.id || needed.contains(&id) {
+            continue;
+        }
+        if let Some(element) = shared.iter().find(|element| element.id == id) {
+            needed.push(id);
+            pending.extend(element.references.iter().cloned());
+        }
+    }
+    needed
+}
+
+/// Every <symbol> of the sprite that has an id
+fn find_symbols(sprite: &str) -> Vec<Element> {
+    find_elements(sprite, Some("symbol"))
+        .into_iter()
+        .filter_map(|markup| {
+            let id = tag_attribute(&markup, "id")?;
+            Some(Element { id, references: references(&markup), markup })
+        })
+        .collect()
+}
+
+/// The ids an element points at through url(#id), href="#id" or xlink:href="#id"
+fn references(markup: &str) -> Vec<String> {
+    let re = Regex::new(r#"url\(\s*['"]?#([^'")\s]+)['"]?\s*\)|href\s*=\s*["']#([^"']+)["']"#).unwrap();
+    re.captures_iter(markup)
+        .filter_map(|reference| reference.get(1).or(reference.get(2)))
+        .map(|id| id.as_str().to_string())
+        .collect()
+}
+
+/// What is between the opening and the closing tag of an element
+fn inner_markup(markup: &str) -> String {
+    let start = markup.find('>').map(|index| index + 1).unwrap_or(0);
+    let end = markup.rfind("</").unwrap_or(markup.len()).max(start);
+    markup[start..end].to_string()
+}
+
+/// Adds the svgs to the sprite sheet of the destination folder, replacing the symbols of the same name,
+/// and writes the Icon component whose name prop lists every symbol of the sheet
+pub fn write_sheet(icons: &[(String, String)], config: &Config) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
+    let sheet_path = config.destination_folder.join(SHEET_FILE_NAME);
+    let component_path = icon_component_path(config);
+    let mut symbols: BTreeMap<String, String> = BTreeMap::new();
+    if sheet_path.exists() {
+        let sheet = fs::read_to_string(&sheet_path)?;
+        if !sheet.starts_with(SHEET_MARKER) {
+            return Err(format!("{:?} was not generated by QuickIcon, so it was left untouched", sheet_path).into());
+        }
+        symbols.extend(find_symbols(&sheet).into_iter().map(|symbol| (symbol.id, symbol.markup)));
+    }
+    if component_path.exists() && !is_generated(&component_path) {
+        return Err(format!("{:?} was not generated by QuickIcon, so it was left untouched", component_path).into());
+    }
+
+    for (name, svg) in icons {
+        let id = format!("{}{}", config.sprite_prefix, icon_id(name, config));
+        let symbol = to_symbol(svg, &id).map_err(|err| format!("{}: {}", name, err))?;
+        symbols.insert(id, symbol);
+    }
+
+    let xlink = if symbols.values().any(|symbol| symbol.contains("xlink:")) {
+        r#" xmlns:xlink="http://www.w3.org/1999/xlink""#
+    } else {
+        ""
+    };
+    let body: String = symbols.values().map(|symbol| format!("  {}\n", symbol)).collect();
+    let sheet = format!(
+        "{}\n<svg xmlns=\"http://www.w3.org/2000/svg\"{} style=\"display: none\">\n{}</svg>\n",
+        SHEET_MARKER, xlink, body
+    );
+
+    let names: Vec<&str> = symbols
+        .keys()
+        .filter_map(|id| id.strip_prefix(config.sprite_prefix.as_str()))
+        .collect();
+    fs::create_dir_all(&config.destination_folder)?;
+    fs::write(&sheet_path, sheet)?;
+    fs::write(&component_path, icon_component(&names, &sprite_url(config), config))?;
+    Ok((sheet_path, component_path))
+}
+
+/// The name of an icon in the sheet: its component name in kebab-case, without the name suffix nor the sprite prefix
+/// e.g ArrowLeftIcon becomes arrow-left, and so does IconArrowLeft coming from an icon-arrow-left symbol
+pub fn icon_id(component_name: &str, config: &Config) -> String {
+    let name = component_name
+        .strip_suffix(config.name_suffix.as_str())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(component_name);
+    let id = to_kebab_case(name);
+    match id.strip_prefix(config.sprite_prefix.as_str()) {
+        Some(unprefixed) if !unprefixed.is_empty() && !config.sprite_prefix.is_empty() => unprefixed.to_string(),
+        _ => id,
+    }
+}
+
+/// The path of the Icon component in the destination folder
+pub fn icon_component_path(config: &Config) -> PathBuf {
+    let extension = if config.is_javascript { "jsx" } else { "tsx" };
+    config.destination_folder.join(format!("{}.{}", ICON_COMPONENT, extension))
+}
+
+/// Turns an svg into a <symbol> of the sheet: the size goes, the viewBox stays so it scales, the ids inside
+/// are prefixed with the symbol id so two icons cannot clash, and the colors follow the color of the Icon
+fn to_symbol(svg: &str, id: &str) -> Result<String, String> {
+    let svg = find_svgs(svg).into_iter().next().ok_or("there is no svg element")?;
+    let view_box = tag_attribute(&svg, "viewBox")
+        .or_else(|| {
+            let width = tag_attribute(&svg, "width")?.trim_end_matches("px").parse::<f64>().ok()?;
+            let height = tag_attribute(&svg, "height")?.trim_end_matches("px").parse::<f64>().ok()?;
+            Some(format!("0 0 {} {}", width, height))
+        })
+        .ok_or("the svg has no viewBox nor a width and height, so it cannot be scaled")?;
+
+    let mut attributes = format!(r#" id="{}" viewBox="{}""#, id, view_box);
+    if let Some(value) = tag_attribute(&svg, "preserveAspectRatio") {
+        attributes.push_str(&format!(r#" preserveAspectRatio="{}""#, value));
+    }
+
+    let mut body = inner_markup(&svg).trim().to_string();
+    let inner_ids: Vec<String> = Regex::new(r#"\sid\s*=\s*["']([^"']+)["']"#)
+        .unwrap()
+        .captures_iter(&body)
+        .map(|found| found[1].to_string())
+        .collect();
+    if !inner_ids.is_empty() {
+        let renamed = |found: &Captures| {
+            let old = found.get(2).unwrap().as_str();
+            if inner_ids.iter().any(|inner_id| inner_id == old) {
+                format!("{}{}-{}{}", &found[1], id, old, &found[3])
+            } else {
+                found[0].to_string()
+            }
+        };
+        body = Regex::new(r#"(\sid\s*=\s*["'])([^"']+)(["'])"#).unwrap().replace_all(&body, renamed).to_string();
+        body = Regex::new(r#"(url\(\s*['"]?#)([^'")\s]+)(['"]?\s*\))"#).unwrap().replace_all(&body, renamed).to_string();
+        body = Regex::new(r#"(href\s*=\s*["']#)([^"']+)(["'])"#).unwrap().replace_all(&body, renamed).to_string();
+    }
+
+    // The same colors as the components turn into the color prop become currentColor
+    let color = Regex::new(r##"\b(fill|stroke)="#([0-9A-Fa-f]{6}|[0-9A-Fa-f]{3})""##).unwrap();
+    body = color
+        .replace_all(&body, |found: &Captures| {
+            if found[2].eq_ignore_ascii_case("fff") || found[2].eq_ignore_ascii_case("ffffff") {
+                found[0].to_string()
+            } else {
+                format!(r#"{}="currentColor""#, &found[1])
+            }
+        })
+        .to_string();
+
+    Ok(format!("<symbol{}>{}</symbol>", attributes, body))
+}
+
+/// The url the sheet is served from: sprite_url when it is set, else the path of the sheet under the public folder
+fn sprite_url(config: &Config) -> String {
+    if !config.sprite_url.is_empty() {
+        return config.sprite_url.clone();
+    }
+
+    let components: Vec<String> = config
+        .destination_folder
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    match components.iter().position(|name| name == "public") {
+        Some(index) => {
+            let mut url = String::new();
+            for name in &components[index + 1..] {
+                url.push('/');
+                url.push_str(name);
+            }
+            format!("{}/{}", url, SHEET_FILE_NAME)
+        },
+        None => {
+            println!(
+                "{} {} is not in a public folder, set sprite_url to the url {} is served from",
+                style("⚠️").yellow(),
+                config.destination_folder.display(),
+                SHEET_FILE_NAME
+            );
+            format!("/{}", SHEET_FILE_NAME)
+        },
+    }
+}
+
+/// The source of the Icon component, its name prop being a union of the names in the sheet
+fn icon_component(names: &[&str], url: &str, config: &Config) -> String {
+    let union = if names.is_empty() {
+        "never".to_string()
+    } else {
+        names.iter().map(|name| format!("\"{}\"", name)).collect::<Vec<String>>().join(" | ")
+    };
+    let example = names.first().copied().unwrap_or("name");
+
+    let (import_line, types, props_type) = if config.is_javascript {
+        (
+            r#"import React from "react";"#.to_string(),
+            format!("/** @typedef {{{}}} IconName */", union),
+            String::new(),
+        )
+    } else {
+        (
+            r#"import React, {SVGProps} from "react";"#.to_string(),
+            format!(
+                r#"export type IconName = {};
+
+interface IconProps extends SVGProps<SVGSVGElement> {{
+    name: IconName;
+    size?: `${{number}}` | number;
+    color?: string;
+}}"#,
+                union
+            ),
+            " : IconProps".to_string(),
+        )
+    };
+
+    format!(
+        r##"{}
+{}
+
+{}
+
+const SPRITE_URL = "{}";
+const PREFIX = "{}";
+
+const {} = ({{
+    name,
+    size = {},
+    color = '{}',
+    ...props
+}}{}) => {{
+    return (
+        <svg width={{size}} height={{size}} color={{color}} {{...props}}>
+            <use href={{`${{SPRITE_URL}}#${{PREFIX}}${{name}}`}} />
+        </svg>
+    );
+}};
+
+export default {};
+
+// Usage examples:
+// <{} name="{}" />
+// <{} name="{}" size={{32}} color="#3B82F6" />
+"##,
+        GENERATED_MARKER,
+        import_line,
+        types,
+        url,
+        config.sprite_prefix,
+        ICON_COMPONENT,
+        config.size,
+        config.color,
+        props_type,
+        ICON_COMPONENT,
+        ICON_COMPONENT,
+        example,
+        ICON_COMPONENT,
+        example
+    )
+}
@@ -0,0 +1,101 @@
+use std::{fs, path::PathBuf};
+
+use resvg::tiny_skia::{Color, Pixmap, PixmapPaint, Transform};
+
+use crate::{
+    convert::SvgToReact,
+    default::Config,
+    raster::{rasterize, substitute_color},
+};
+
+/// The size both svgs are rendered at, in pixels
+static VERIFY_SIZE: u32 = 128;
+
+/// How far apart the channels of two pixels can be before they count as different, so anti-aliasing passes
+static PIXEL_TOLERANCE: u8 = 32;
+
+/// How a component renders compared to the svg it came from
+pub struct Verification {
+    /// The share of the drawn pixels that differ, in percent
+    pub difference: f64,
+    pub threshold: f64,
+    /// The image of the svg, the component and the differing pixels side by side, written when the check fails
+    pub diff_path: Option<PathBuf>,
+}
+
+impl Verification {
+    pub fn passed(&self) -> bool {
+        self.difference <= self.threshold
+    }
+
+    /// A one line report e.g 0.12% of the pixels differ (threshold 0.5%)
+    pub fn summary(&self) -> String {
+        let report = format!("{:.2}% of the pixels differ (threshold {}%)", self.difference, self.threshold);
+        match &self.diff_path {
+            Some(path) => format!("{}, see {}", report, path.display()),
+            None => report,
+        }
+    }
+}
+
+/// Renders the svg and the svg of the component, with its default size and color put back, and compares them.
+/// Both are painted with the default color first, as the component turns the colors of the svg into it.
+pub fn verify(name: &str, svg: &str, config: &Config, threshold: f64) -> Result<Verification, Box<dyn std::error::Error>> {
+    let converted = SvgToReact::new(svg.to_string(), name.to_string(), config.clone()).render_svg()?;
+    let original = rasterize(&substitute_color(svg, &config.color), VERIFY_SIZE)?;
+    let converted = rasterize(&substitute_color(&converted, &config.color), VERIFY_SIZE)
+        .map_err(|err| format!("The converted svg is broken: {}", err))?;
+
+    let mut drawn = 0;
+    let mut different = Vec::new();
+    for (index, (before, after)) in original.pixels().iter().zip(converted.pixels()).enumerate() {
+        if before.alpha() == 0 && after.alpha() == 0 {
+            continue;
+        }
+        drawn += 1;
+        let channels = [
+            (before.red(), after.red()),
+            (before.green(), after.green()),
+            (before.blue(), after.blue()),
+            (before.alpha(), after.alpha()),
+        ];
+        if channels.iter().any(|(before, after)| before.abs_diff(*after) > PIXEL_TOLERANCE) {
+            different.push(index);
+        }
+    }
+    let difference = if drawn == 0 { 0.0 } else { different.len() as f64 * 100.0 / drawn as f64 };
+
+    let diff_path = config.destination_folder.join(format!("{}.diff.png", name));
+    let mut verification = Verification { difference, threshold, diff_path: None };
+    if verification.passed() {
+        // A diff left over from an earlier failure would only mislead
+        let _ = fs::remove_file(&diff_path);
+    } else {
+        fs::create_dir_all(&config.destination_folder)?;
+        fs::write(&diff_path, diff_image(&original, &converted, &different)?.encode_png()?)?;
+        verification.diff_path = Some(diff_path);
+    }
+    Ok(verification)
+}
+
+/// The svg, the component and the differing pixels in red over the shape in grey, side by side on white
+fn diff_image(original: &Pixmap, converted: &Pixmap, different: &[usize]) -> Result<Pixmap, Box<dyn std::error::Error>> {
+    let size = VERIFY_SIZE as usize;
+    let mut image = Pixmap::new(VERIFY_SIZE * 3, VERIFY_SIZE).ok_or("The diff image could not be created")?;
+    image.fill(Color::WHITE);
+    image.draw_pixmap(0, 0, original.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+    image.draw_pixmap(VERIFY_SIZE as i32, 0, converted.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+
+    let data = image.data_mut();
+    for (index, pixel) in original.pixels().iter().enumerate() {
+        if pixel.alpha() > 0 {
+            let offset = ((index / size) * size * 3 + 2 * size + index % size) * 4;
+            data[offset..offset + 4].copy_from_slice(&[210, 210, 210, 255]);
+        }
+    }
+    for index in different {
+        let offset = ((index / size) * size * 3 + 2 * size + index % size) * 4;
+        data[offset..offset + 4].copy_from_slice(&[255, 0, 0, 255]);
+    }
+    Ok(image)
+}
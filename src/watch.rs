@@ -0,0 +1,197 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::mpsc, time::Duration};
+
+use dialoguer::{console::style, Input};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebouncedEventKind};
+
+use crate::{
+    content::{read_clipboard_svg, Source},
+    convert::{component_file_path, is_generated, SvgToReact},
+    default::Config,
+    lockfile::{record_generated, remove_generated},
+    parser::{component_name_parser, svg_validator},
+};
+
+/// Polls the clipboard and converts every new svg that shows up until Ctrl-C is pressed
+pub async fn watch_clipboard(root: &Path, config: Config, auto_name: bool, interval: u64) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut generated: Vec<PathBuf> = Vec::new();
+    let mut last_seen = read_clipboard_svg().ok();
+
+    println!(
+        "{}",
+        style("👀 Watching the clipboard for svgs, press Ctrl-C to stop").cyan()
+    );
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            _ = tokio::time::sleep(Duration::from_millis(interval)) => {}
+        }
+
+        let content = match read_clipboard_svg() {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        if last_seen.as_deref() == Some(content.as_str()) {
+            continue;
+        }
+        last_seen = Some(content.clone());
+
+        let suggested_name = next_icon_name(&config);
+        let icon_name = if auto_name {
+            suggested_name
+        } else {
+            // Ctrl-C while the prompt is open ends the session too
+            match Input::<String>::new()
+                .with_prompt("New svg on the clipboard, component name")
+                .default(suggested_name)
+                .validate_with(|input: &String| component_name_parser(input, &config.name_suffix).map(|_| ()))
+                .interact_text()
+            {
+                Ok(name) => name,
+                Err(_) => break,
+            }
+        };
+        let icon_name = match component_name_parser(&icon_name, &config.name_suffix) {
+            Ok(component_name) => {
+                if let Some(explanation) = component_name.explain() {
+                    println!("{}", style(explanation).yellow());
+                }
+                component_name.name
+            },
+            Err(err) => {
+                println!("{}", style(err).red());
+                continue;
+            }
+        };
+
+        match SvgToReact::new(content.clone(), icon_name.clone(), config.clone()).convert_and_save() {
+            Ok(path) => {
+                if let Err(err) = record_generated(root, &path, &icon_name, Source::Clipboard, &content, &config) {
+                    println!("The icon could not be recorded in the lockfile: {}", style(err).yellow());
+                }
+                generated.push(path);
+                print_generated(&generated);
+            },
+            Err(err) => {
+                println!("An error occurred when generating the component: {}", style(err).red());
+            }
+        }
+    }
+
+    Ok(generated)
+}
+
+/// Prints every component generated in this session so far
+fn print_generated(generated: &[PathBuf]) {
+    println!("{}", style(format!("🎉 {} icon(s) generated so far:", generated.len())).green());
+    for path in generated {
+        println!("   {}", path.display());
+    }
+}
+
+/// Finds the first IconN name that does not exist yet in the destination folder.
+/// With a name suffix the parser adds it to SvgN instead e.g Svg1Icon, which reads better than Icon1Icon.
+fn next_icon_name(config: &Config) -> String {
+    let base_name = if config.name_suffix.is_empty() { "Icon" } else { "Svg" };
+    (1..)
+        .filter_map(|n| component_name_parser(&format!("{} {}", base_name, n), &config.name_suffix).ok())
+        .map(|component_name| component_name.name)
+        .find(|name| !component_file_path(name, config).exists())
+        .unwrap()
+}
+
+/// Watches a folder of svgs and regenerates, or removes, the matching component whenever one changes.
+/// Saves that land within the debounce window are handled as a single change.
+pub fn watch_directory(root: &Path, source: &Path, config: Config, debounce: u64) -> Result<(), Box<dyn std::error::Error>> {
+    if !source.is_dir() {
+        return Err(format!("The source folder {:?} does not exist", source).into());
+    }
+
+    // Reading an svg counts as an event too, so remember what was converted to skip unchanged files
+    let mut converted: HashMap<PathBuf, String> = HashMap::new();
+    let (sender, receiver) = mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(debounce), sender)?;
+    debouncer.watcher().watch(source, RecursiveMode::NonRecursive)?;
+
+    println!(
+        "{}",
+        style(format!("👀 Watching {} for svg changes, press Ctrl-C to stop", source.display())).cyan()
+    );
+
+    for result in receiver {
+        let events = match result {
+            Ok(events) => events,
+            Err(err) => {
+                println!("An error occurred while watching the folder: {}", style(err).red());
+                continue;
+            }
+        };
+
+        // Continuous events are followed by a final one once the burst settles, so only that one counts
+        for event in events.into_iter().filter(|event| event.kind == DebouncedEventKind::Any) {
+            let is_svg = event.path.extension().map(|extension| extension == "svg").unwrap_or(false);
+            if is_svg {
+                sync_component(root, &event.path, &config, &mut converted);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Brings the component generated from an svg in line with it: regenerated if the svg exists, removed if not
+fn sync_component(root: &Path, svg_path: &Path, config: &Config, converted: &mut HashMap<PathBuf, String>) {
+    let component_name = match svg_path.file_stem().map(|stem| component_name_parser(&stem.to_string_lossy(), &config.name_suffix)) {
+        Some(Ok(component_name)) => component_name.name,
+        Some(Err(err)) => {
+            println!("{} was skipped: {}", svg_path.display(), style(err).yellow());
+            return;
+        },
+        None => return,
+    };
+
+    if !svg_path.exists() {
+        converted.remove(svg_path);
+        let component_path = component_file_path(&component_name, config);
+        if is_generated(&component_path) {
+            match fs::remove_file(&component_path) {
+                Ok(()) => {
+                    println!("{}", style(format!("🗑️  Removed {}", component_path.display())).yellow());
+                    if let Err(err) = remove_generated(root, &component_path) {
+                        println!("The icon could not be removed from the lockfile: {}", style(err).yellow());
+                    }
+                },
+                Err(err) => println!("An error occurred when removing {}: {}", component_path.display(), style(err).red()),
+            }
+        }
+        return;
+    }
+
+    let content = match fs::read_to_string(svg_path) {
+        Ok(content) if converted.get(svg_path) == Some(&content) => return,
+        Ok(content) if svg_validator(&content) => content,
+        Ok(_) => {
+            println!("{} does not contain a valid svg element, skipping it", svg_path.display());
+            return;
+        },
+        Err(err) => {
+            println!("An error occurred while reading {}: {}", svg_path.display(), style(err).red());
+            return;
+        }
+    };
+
+    converted.insert(svg_path.to_path_buf(), content.clone());
+    match SvgToReact::new(content.clone(), component_name.clone(), config.clone()).convert_and_overwrite() {
+        Ok(path) => {
+            println!("{}", style(format!("🔁 Regenerated {}", path.display())).green());
+            let source = Source::File { path: svg_path.to_string_lossy().to_string() };
+            if let Err(err) = record_generated(root, &path, &component_name, source, &content, config) {
+                println!("The icon could not be recorded in the lockfile: {}", style(err).yellow());
+            }
+        },
+        Err(err) => println!("An error occurred when generating the component: {}", style(err).red()),
+    }
+}